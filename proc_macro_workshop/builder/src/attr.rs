@@ -0,0 +1,391 @@
+//! Parsing of the `#[builder(...)]` attributes on the struct and its fields.
+//!
+//! Every option is written as `key`, `key = literal` or `key(...)`. Unknown
+//! keys, repeated keys and literals of the wrong kind are reported as
+//! `syn::Error`s pointing at the offending tokens.
+
+/// Options given through `#[builder(...)]` on the struct itself.
+#[derive(Default)]
+pub(crate) struct StructAttrs {
+    /// `default`: unset fields are taken from the struct's `Default` impl.
+    pub(crate) default: bool,
+    /// `pattern = "..."`: how setters and `build` take the builder.
+    pub(crate) pattern: Pattern,
+    /// `setter(...)`: defaults for the setters of every field.
+    pub(crate) setter: SetterAttrs,
+    /// `validate = "..."`: path of a function validating the whole builder.
+    pub(crate) validate: Option<syn::Path>,
+    /// `typestate`: missing required fields are compile errors rather than
+    /// build errors.
+    pub(crate) typestate: bool,
+    /// `const`: `builder`, the setters and `build` are `const fn`s, and
+    /// `build` panics on missing fields.
+    pub(crate) const_fn: bool,
+    /// `name = "..."`: name of the builder instead of `{Struct}Builder`.
+    pub(crate) name: Option<syn::Ident>,
+    /// `vis = "..."`: visibility of the builder instead of the struct's own.
+    pub(crate) vis: Option<syn::Visibility>,
+    /// `derive(...)`: traits to derive for the builder besides `Debug`.
+    pub(crate) derive: Vec<syn::Path>,
+    /// `try_setter`: every field that can have one gets a `try_` setter.
+    pub(crate) try_setter: bool,
+    /// `doc = "..."`: documentation of the builder struct.
+    pub(crate) doc: Option<syn::LitStr>,
+}
+
+/// Receiver style of the generated setters and `build`.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub(crate) enum Pattern {
+    /// `&mut self -> &mut Self` setters, `build(&self)` clones every field.
+    #[default]
+    Mutable,
+    /// `&self -> Self` setters on a cloned builder, `build(&self)` clones.
+    Immutable,
+    /// `self -> Self` setters, `build(self)` moves fields without cloning.
+    Owned,
+}
+
+/// Options given through `#[builder(...)]` on a single field.
+#[derive(Default)]
+pub(crate) struct FieldAttrs {
+    /// `each = "..."`: name of the one-element-at-a-time setter.
+    pub(crate) each: Option<syn::Ident>,
+    /// `name = "..."`: overrides the setter name, mostly for tuple fields.
+    pub(crate) name: Option<syn::Ident>,
+    /// `default`, `default = "..."` or `default_with = "..."`: value used
+    /// when the field is unset.
+    pub(crate) default: Option<FieldDefault>,
+    /// `setter(...)`: overrides the struct-level setter options.
+    pub(crate) setter: SetterAttrs,
+    /// `check = "..."`: path of a function validating the field's value.
+    pub(crate) check: Option<syn::Path>,
+    /// `skip` or `setter(skip)`: the field is left out of the builder and
+    /// always takes its default value.
+    pub(crate) skip: bool,
+    /// `sub_builder` or `sub_builder = "..."`: the field is built through its
+    /// own builder, which has to use the mutable pattern.
+    pub(crate) sub_builder: Option<SubBuilder>,
+    /// `optional` or `required`: whether the field may be left unset,
+    /// regardless of whether its type looks like an `Option`.
+    pub(crate) optional: Option<bool>,
+    /// `try_setter` or `try_setter = bool`: a `try_` setter taking
+    /// `impl TryInto<T>` is generated next to the normal one.
+    pub(crate) try_setter: Option<bool>,
+    /// `lazy`: the setter takes a closure that `build` calls.
+    pub(crate) lazy: bool,
+}
+
+/// How `build` fills in a field that was never set.
+pub(crate) enum FieldDefault {
+    /// `#[builder(default)]`: `Default::default()`.
+    Trait,
+    /// `#[builder(default = "...")]`: an arbitrary expression.
+    Expr(syn::Expr),
+    /// `#[builder(default_with = "...")]`: a function called without
+    /// arguments.
+    With(syn::Path),
+}
+
+/// Builder type of a field marked `sub_builder`.
+pub(crate) enum SubBuilder {
+    /// `#[builder(sub_builder)]`: `{Type}Builder` with the field type's
+    /// generic arguments.
+    Derived,
+    /// `#[builder(sub_builder = "...")]`: a builder that was given another
+    /// name.
+    Named(syn::Type),
+}
+
+/// Options given through `setter(...)`, either on the struct or on a field.
+#[derive(Default)]
+pub(crate) struct SetterAttrs {
+    /// `into` or `into = bool`: setters take `impl Into<T>`.
+    pub(crate) into: Option<bool>,
+    /// `strip_option` or `strip_option = bool`: setters of `Option<T>` fields
+    /// take `T`. This is the default.
+    pub(crate) strip_option: Option<bool>,
+    /// `skip` or `skip = bool`: no setter is generated. Fields only.
+    pub(crate) skip: Option<bool>,
+}
+
+pub(crate) fn parse_struct_attrs(attrs: &[syn::Attribute]) -> syn::Result<StructAttrs> {
+    let mut struct_attrs = StructAttrs::default();
+    let mut seen = Vec::new();
+    // options that do not go along with `typestate`, and further ones that
+    // do not go along with `const`
+    let mut conflicts = Vec::new();
+    let mut const_conflicts = Vec::new();
+
+    for (_, meta) in builder_metas(attrs)? {
+        check_duplicate(&mut seen, meta.path())?;
+
+        match key(meta.path()).as_str() {
+            "default" => {
+                struct_attrs.default = flag(&meta)?;
+                const_conflicts.push(meta);
+            }
+            "setter" => {
+                parse_setter_attrs(list(&meta)?, &mut struct_attrs.setter, false)?;
+                if struct_attrs.setter.into == Some(true) {
+                    const_conflicts.push(meta);
+                }
+            }
+            "typestate" => {
+                struct_attrs.typestate = flag(&meta)?;
+                const_conflicts.push(meta);
+            }
+            "try_setter" => {
+                struct_attrs.try_setter = flag(&meta)?;
+                const_conflicts.push(meta);
+            }
+            "const" => struct_attrs.const_fn = flag(&meta)?,
+            "name" => struct_attrs.name = Some(string(&meta)?.parse()?),
+            "vis" => struct_attrs.vis = Some(string(&meta)?.parse()?),
+            "doc" => struct_attrs.doc = Some(string(&meta)?.clone()),
+            "derive" => {
+                for nested in &list(&meta)?.nested {
+                    match nested {
+                        syn::NestedMeta::Meta(syn::Meta::Path(path)) => {
+                            struct_attrs.derive.push(path.clone())
+                        }
+                        _ => return Err(syn::Error::new_spanned(nested, "expected a trait")),
+                    }
+                }
+            }
+            "validate" => {
+                struct_attrs.validate = Some(string(&meta)?.parse()?);
+                conflicts.push(meta);
+            }
+            "pattern" => {
+                let lit = string(&meta)?;
+                struct_attrs.pattern = match lit.value().as_str() {
+                    "mutable" => Pattern::Mutable,
+                    "immutable" => Pattern::Immutable,
+                    "owned" => Pattern::Owned,
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            lit,
+                            r#"expected one of "mutable", "immutable" or "owned""#,
+                        ))
+                    }
+                };
+                if struct_attrs.pattern != Pattern::Owned {
+                    conflicts.push(meta);
+                }
+            }
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    meta.path(),
+                    "unknown builder option",
+                ))
+            }
+        }
+    }
+
+    if struct_attrs.const_fn {
+        // a `const fn` setter cannot take `&mut self` and return it
+        struct_attrs.pattern = Pattern::Owned;
+
+        if let Some(meta) = conflicts.iter().chain(&const_conflicts).next() {
+            return Err(syn::Error::new_spanned(
+                meta,
+                format!("`{}` cannot be used with `const`", key(meta.path())),
+            ));
+        }
+    }
+
+    if struct_attrs.typestate {
+        // every required setter changes the builder's type, so it has to be
+        // taken by value
+        struct_attrs.pattern = Pattern::Owned;
+
+        if let Some(meta) = conflicts.first() {
+            return Err(syn::Error::new_spanned(
+                meta,
+                format!("`{}` cannot be used with `typestate`", key(meta.path())),
+            ));
+        }
+    }
+
+    Ok(struct_attrs)
+}
+
+pub(crate) fn parse_field_attrs(field: &syn::Field) -> syn::Result<FieldAttrs> {
+    let mut attrs = FieldAttrs::default();
+    let mut seen = Vec::new();
+
+    for (builder, meta) in builder_metas(&field.attrs)? {
+        check_duplicate(&mut seen, meta.path())?;
+
+        match key(meta.path()).as_str() {
+            "each" => attrs.each = Some(string(&meta)?.parse()?),
+            "name" => attrs.name = Some(string(&meta)?.parse()?),
+            "check" => attrs.check = Some(string(&meta)?.parse()?),
+            "setter" => parse_setter_attrs(list(&meta)?, &mut attrs.setter, true)?,
+            "skip" => attrs.skip = flag(&meta)?,
+            "try_setter" => attrs.try_setter = Some(flag(&meta)?),
+            "lazy" => attrs.lazy = flag(&meta)?,
+            "default" | "default_with" if attrs.default.is_some() => {
+                return Err(syn::Error::new_spanned(
+                    meta,
+                    "`default` cannot be used with `default_with`",
+                ))
+            }
+            "default_with" => attrs.default = Some(FieldDefault::With(string(&meta)?.parse()?)),
+            "optional" | "required" if attrs.optional.is_some() => {
+                return Err(syn::Error::new_spanned(
+                    meta,
+                    "`optional` cannot be used with `required`",
+                ))
+            }
+            "optional" => attrs.optional = Some(flag(&meta)?),
+            "required" => attrs.optional = Some(!flag(&meta)?),
+            "sub_builder" => {
+                attrs.sub_builder = Some(match meta {
+                    syn::Meta::Path(_) => SubBuilder::Derived,
+                    _ => SubBuilder::Named(string(&meta)?.parse()?),
+                })
+            }
+            "default" => {
+                attrs.default = Some(match meta {
+                    syn::Meta::Path(_) => FieldDefault::Trait,
+                    _ => FieldDefault::Expr(string(&meta)?.parse()?),
+                })
+            }
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    builder,
+                    r#"expected `builder(each = "...")`"#,
+                ))
+            }
+        }
+    }
+
+    if attrs.setter.skip == Some(true) {
+        attrs.skip = true;
+    }
+
+    Ok(attrs)
+}
+
+fn parse_setter_attrs(
+    list: &syn::MetaList,
+    attrs: &mut SetterAttrs,
+    on_field: bool,
+) -> syn::Result<()> {
+    let mut seen = Vec::new();
+
+    for nested in &list.nested {
+        let meta = match nested {
+            syn::NestedMeta::Meta(meta) => meta,
+            syn::NestedMeta::Lit(lit) => {
+                return Err(syn::Error::new_spanned(lit, "expected a setter option"))
+            }
+        };
+        check_duplicate(&mut seen, meta.path())?;
+
+        match key(meta.path()).as_str() {
+            "into" => attrs.into = Some(flag(meta)?),
+            "strip_option" => attrs.strip_option = Some(flag(meta)?),
+            "skip" if on_field => attrs.skip = Some(flag(meta)?),
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    meta.path(),
+                    "unknown setter option",
+                ))
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// All options of all `#[builder(...)]` attributes, each paired with the
+/// `builder(...)` list it was written in.
+fn builder_metas(attrs: &[syn::Attribute]) -> syn::Result<Vec<(syn::MetaList, syn::Meta)>> {
+    let mut metas = Vec::new();
+
+    for attr in attrs.iter().filter(|a| a.path.is_ident("builder")) {
+        let list = match attr.parse_meta()? {
+            syn::Meta::List(list) => list,
+            meta => return Err(syn::Error::new_spanned(meta, "expected `builder(...)`")),
+        };
+
+        for nested in &list.nested {
+            match nested {
+                syn::NestedMeta::Meta(meta) => metas.push((list.clone(), meta.clone())),
+                syn::NestedMeta::Lit(lit) => {
+                    return Err(syn::Error::new_spanned(lit, "expected a builder option"))
+                }
+            }
+        }
+    }
+
+    Ok(metas)
+}
+
+fn key(path: &syn::Path) -> String {
+    quote::ToTokens::to_token_stream(path)
+        .to_string()
+        .replace(' ', "")
+}
+
+fn check_duplicate(seen: &mut Vec<String>, path: &syn::Path) -> syn::Result<()> {
+    let key = key(path);
+    if seen.contains(&key) {
+        return Err(syn::Error::new_spanned(
+            path,
+            format!("duplicate `{}` option", key),
+        ));
+    }
+    seen.push(key);
+
+    Ok(())
+}
+
+/// `key` or `key = true|false`.
+fn flag(meta: &syn::Meta) -> syn::Result<bool> {
+    match meta {
+        syn::Meta::Path(_) => Ok(true),
+        syn::Meta::NameValue(syn::MetaNameValue {
+            lit: syn::Lit::Bool(lit),
+            ..
+        }) => Ok(lit.value),
+        syn::Meta::NameValue(nv) => Err(syn::Error::new_spanned(
+            &nv.lit,
+            "expected `true` or `false`",
+        )),
+        syn::Meta::List(_) => Err(syn::Error::new_spanned(
+            meta,
+            format!("expected `{}` or `{0} = bool`", key(meta.path())),
+        )),
+    }
+}
+
+/// `key = "..."`.
+fn string(meta: &syn::Meta) -> syn::Result<&syn::LitStr> {
+    match meta {
+        syn::Meta::NameValue(syn::MetaNameValue {
+            lit: syn::Lit::Str(lit),
+            ..
+        }) => Ok(lit),
+        syn::Meta::NameValue(nv) => {
+            Err(syn::Error::new_spanned(&nv.lit, "expected string literal"))
+        }
+        _ => Err(syn::Error::new_spanned(
+            meta,
+            format!("expected `{} = \"...\"`", key(meta.path())),
+        )),
+    }
+}
+
+/// `key(...)`.
+fn list(meta: &syn::Meta) -> syn::Result<&syn::MetaList> {
+    match meta {
+        syn::Meta::List(list) => Ok(list),
+        _ => Err(syn::Error::new_spanned(
+            meta,
+            format!("expected `{}(...)`", key(meta.path())),
+        )),
+    }
+}
@@ -1,171 +1,1511 @@
+mod attr;
+
+use attr::{FieldDefault, Pattern, SubBuilder};
+
 #[proc_macro_derive(Builder, attributes(builder))]
 pub fn derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = syn::parse_macro_input!(input as syn::DeriveInput);
 
-    let input_ident = &input.ident;
-    let builder_ident = quote::format_ident!("{}Builder", &input.ident);
+    expand(&input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
 
+/// A struct field as seen by the builder.
+struct Field<'a> {
+    /// How the field is addressed when constructing the target struct.
+    member: syn::Member,
+    /// Name of the field on the builder, and of its setter.
+    ident: syn::Ident,
+    ty: &'a syn::Type,
+    /// The field may be left unset, `build` then leaves it `None`.
+    optional: bool,
+    /// The `T` of an optional field spelled `Option<T>`.
+    option: Option<&'a syn::Type>,
+    each: Option<Each<'a>>,
+    default: Option<FieldDefault>,
+    /// Setters take `impl Into<T>` instead of `T`.
+    into: bool,
+    /// The setter of an `Option<T>` field takes `T` instead of `Option<T>`.
+    strip_option: bool,
+    /// `fn(&T) -> Result<(), E>` run on the final value inside `build`.
+    check: Option<syn::Path>,
+    /// Left out of the builder, `build` always uses the default value.
+    skip: bool,
+    /// Builder type of a field that is built through its own builder.
+    sub_builder: Option<syn::Type>,
+    /// A `try_` setter is generated next to the normal one.
+    try_setter: bool,
+    /// The setter takes a closure producing the value, called in `build`.
+    lazy: bool,
+    /// The field's `///` comments, repeated on its setters.
+    docs: Vec<&'a syn::Attribute>,
+    /// Type parameter tracking whether a required field of a typestate
+    /// builder was set: `()` before its setter is called, `(T,)` after.
+    state: Option<syn::Ident>,
+}
+
+/// The one-element-at-a-time setter of a collection field.
+struct Each<'a> {
+    /// Name of the setter.
+    ident: syn::Ident,
+    /// Key type, for maps whose setter takes a key and a value.
+    key: Option<&'a syn::Type>,
+    /// Element type, or value type for maps.
+    value: &'a syn::Type,
+}
+
+fn expand(input: &syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
     // extract relevant informations
     // -----------------------------
-    let raw_fields = match input.data {
-        syn::Data::Struct(syn::DataStruct {
-            fields: syn::Fields::Named(n),
-            ..
-        }) => n,
-        _ => unimplemented!(),
+    let struct_attrs = attr::parse_struct_attrs(&input.attrs)?;
+
+    let input_ident = &input.ident;
+    let builder_ident = match &struct_attrs.name {
+        Some(name) => name.clone(),
+        None => quote::format_ident!("{}Builder", &input.ident),
     };
+    let error_ident = quote::format_ident!("{}BuildError", &input.ident);
+    let vis = struct_attrs.vis.as_ref().unwrap_or(&input.vis);
 
-    let fields = raw_fields.named.iter().filter_map(|f| {
-        f.ident.as_ref().map(|ident|
-                             (ident, &f.ty, inner_ty(&f.ty, "Option"), inner_for_attr(f)))
-    });
+    let raw_fields = match &input.data {
+        syn::Data::Struct(data) => &data.fields,
+        syn::Data::Enum(data) => {
+            return Err(syn::Error::new_spanned(
+                data.enum_token,
+                "Builder can only be derived for structs, not enums",
+            ))
+        }
+        syn::Data::Union(data) => {
+            return Err(syn::Error::new_spanned(
+                data.union_token,
+                "Builder can only be derived for structs, not unions",
+            ))
+        }
+    };
+
+    let fields = raw_fields
+        .iter()
+        .enumerate()
+        .map(|(index, f)| {
+            let attrs = attr::parse_field_attrs(f)?;
+
+            let (member, ident) = match &f.ident {
+                Some(ident) => (syn::Member::Named(ident.clone()), ident.clone()),
+                None => (
+                    syn::Member::Unnamed(syn::Index::from(index)),
+                    quote::format_ident!("_{}", index),
+                ),
+            };
+            let ident = attrs.name.unwrap_or(ident);
+
+            // `optional` and `required` override what the type looks like,
+            // e.g. for type aliases
+            let optional = attrs.optional.unwrap_or_else(|| option_inner_ty(&f.ty).is_some());
+            let option = option_inner_ty(&f.ty).filter(|_| optional);
+            let required = !optional
+                && attrs.each.is_none()
+                && attrs.default.is_none()
+                && !attrs.skip
+                && attrs.sub_builder.is_none()
+                && !struct_attrs.default;
+
+            // skipped fields without a default of their own fall back to the
+            // struct's `Default` impl if there is one, else to the field's
+            let default = match attrs.default {
+                None if attrs.skip && !struct_attrs.default => Some(FieldDefault::Trait),
+                default => default,
+            };
+
+            let each = match attrs.each {
+                Some(_) if attrs.skip => {
+                    return Err(syn::Error::new_spanned(
+                        &f.ty,
+                        "`each` cannot be used on skipped fields",
+                    ))
+                }
+                Some(_) if optional => {
+                    return Err(syn::Error::new_spanned(
+                        &f.ty,
+                        "`each` cannot be used on `Option` fields",
+                    ))
+                }
+                Some(ident) => match collection_item_tys(&f.ty).as_slice() {
+                    [key, value, ..] if is_map(&f.ty) => Some(Each {
+                        ident,
+                        key: Some(key),
+                        value,
+                    }),
+                    [value, ..] => Some(Each {
+                        ident,
+                        key: None,
+                        value,
+                    }),
+                    [] => {
+                        return Err(syn::Error::new_spanned(
+                            &f.ty,
+                            "`each` can only be used on collections such as `Vec<T>` or `BTreeMap<K, V>`",
+                        ))
+                    }
+                },
+                None => None,
+            };
+
+            let sub_builder = match attrs.sub_builder {
+                Some(_) if attrs.skip || each.is_some() || optional => {
+                    return Err(syn::Error::new_spanned(
+                        &f.ty,
+                        "`sub_builder` cannot be used on skipped, `each` or `Option` fields",
+                    ))
+                }
+                Some(SubBuilder::Named(ty)) => Some(ty),
+                Some(SubBuilder::Derived) => match &f.ty {
+                    syn::Type::Path(ty) if ty.qself.is_none() => {
+                        let mut ty = ty.clone();
+                        let last = ty.path.segments.last_mut().unwrap();
+                        last.ident = quote::format_ident!("{}Builder", last.ident);
+                        Some(syn::Type::Path(ty))
+                    }
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            &f.ty,
+                            r#"expected a struct deriving `Builder`, or `sub_builder = "..."`"#,
+                        ))
+                    }
+                },
+                None => None,
+            };
+
+            if attrs.try_setter == Some(true) && (each.is_some() || sub_builder.is_some()) {
+                return Err(syn::Error::new_spanned(
+                    &f.ty,
+                    "`try_setter` cannot be used on `each` or `sub_builder` fields",
+                ));
+            }
+            let try_setter = attrs.try_setter.unwrap_or(struct_attrs.try_setter)
+                && each.is_none()
+                && sub_builder.is_none()
+                && !attrs.lazy;
+
+            // closures can only be called once, so `build` has to own them
+            if attrs.lazy {
+                let conflict = if attrs.skip || each.is_some() || sub_builder.is_some() {
+                    Some("`lazy` cannot be used on skipped, `each` or `sub_builder` fields")
+                } else if struct_attrs.typestate {
+                    Some("`lazy` cannot be used with `typestate`")
+                } else if struct_attrs.pattern != Pattern::Owned {
+                    Some(r#"`lazy` fields need `pattern = "owned"`"#)
+                } else if mentions_lifetime(&f.ty, &input.generics) {
+                    Some("`lazy` fields cannot borrow, their closures have to be `'static`")
+                } else {
+                    None
+                };
+                if let Some(msg) = conflict {
+                    return Err(syn::Error::new_spanned(&f.ty, msg));
+                }
+            }
+
+            // `const fn`s can neither call trait methods nor run destructors,
+            // which rules out most options and any field type that might
+            // need dropping when its setter overwrites it
+            if struct_attrs.const_fn {
+                let into = attrs.setter.into.or(struct_attrs.setter.into);
+                let conflict = [
+                    (each.is_some(), "`each`"),
+                    (attrs.check.is_some(), "`check`"),
+                    (sub_builder.is_some(), "`sub_builder`"),
+                    (into == Some(true), "`setter(into)`"),
+                    (try_setter, "`try_setter`"),
+                    (matches!(default, Some(FieldDefault::Trait)), "`Default::default()`"),
+                    (attrs.lazy, "`lazy`"),
+                ]
+                .into_iter()
+                .find(|(conflict, _)| *conflict);
+                if let Some((_, option)) = conflict {
+                    return Err(syn::Error::new_spanned(
+                        &f.ty,
+                        format!("{} cannot be used in a `const` builder", option),
+                    ));
+                }
+                if !is_const_safe(&f.ty) {
+                    return Err(syn::Error::new_spanned(
+                        &f.ty,
+                        "`const` builders only support primitive, reference, array, tuple and `Option` fields",
+                    ));
+                }
+            }
+
+            let state = (required && struct_attrs.typestate).then(|| camel_ident("__", &ident));
+
+            Ok(Field {
+                member,
+                ident,
+                ty: &f.ty,
+                optional,
+                option,
+                each,
+                default,
+                into: attrs
+                    .setter
+                    .into
+                    .or(struct_attrs.setter.into)
+                    .unwrap_or(false),
+                strip_option: attrs
+                    .setter
+                    .strip_option
+                    .or(struct_attrs.setter.strip_option)
+                    .unwrap_or(true),
+                check: attrs.check,
+                skip: attrs.skip,
+                sub_builder,
+                try_setter,
+                lazy: attrs.lazy,
+                docs: f.attrs.iter().filter(|a| a.path.is_ident("doc")).collect(),
+                state,
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    // fields the builder stores and has setters for
+    let stored: Vec<&Field> = fields.iter().filter(|f| !f.skip).collect();
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let generics = &input.generics;
+
+    // typestate builders carry one extra type parameter per required field,
+    // defaulting to the "unset" state
+    let mut builder_generics = input.generics.clone();
+    builder_generics
+        .params
+        .extend(
+            fields
+                .iter()
+                .filter_map(|f| f.state.as_ref())
+                .map(|state| -> syn::GenericParam {
+                    syn::parse_quote! { #state = () }
+                }),
+        );
+    let (builder_impl_generics, builder_ty_generics, _) = builder_generics.split_for_impl();
+    let generic_args = generic_args(generics);
 
     // construct token-streams
     // -----------------------
-    let ts_builder_def = fields
-        .clone()
-        .map(|(name, ty, option, attr)| match (option, attr) {
-            (None, None) => quote::quote! { #name: std::option::Option<#ty> },
-            (Some(ty), None) => quote::quote! { #name: std::option::Option<#ty> },
-            (None, Some((_, ty))) => quote::quote! { #name: std::vec::Vec<#ty> },
-            _ => unimplemented!(),
-        });
+    // types of the fields as stored in the builder
+    let stored_tys: Vec<_> = stored
+        .iter()
+        .map(|f| {
+            if let Some(state) = &f.state {
+                return quote::quote! { #state };
+            }
+            if let Some(sub_builder) = &f.sub_builder {
+                return quote::quote! { std::option::Option<#sub_builder> };
+            }
+            let ty = f.ty;
+            if f.lazy {
+                return quote::quote! {
+                    std::option::Option<std::boxed::Box<dyn std::ops::FnOnce() -> #ty>>
+                };
+            }
+            match (f.optional, &f.each) {
+                (false, None) => quote::quote! { std::option::Option<#ty> },
+                (true, None) | (false, Some(_)) => quote::quote! { #ty },
+                (true, Some(_)) => {
+                    unreachable!("`each` on `Option` fields is rejected while parsing")
+                }
+            }
+        })
+        .collect();
+
+    // type parameters and lifetimes may only be used by fields the builder
+    // does not store as-is, so keep those alive through a marker field
+    let unused = |ident: &syn::Ident| {
+        !stored_tys
+            .iter()
+            .any(|ty| mentions_ident(ty.clone(), ident))
+    };
+    let phantom_lifetimes: Vec<_> = generics
+        .lifetimes()
+        .map(|l| &l.lifetime)
+        .filter(|l| unused(&l.ident))
+        .collect();
+    let phantom_params: Vec<_> = generics
+        .type_params()
+        .map(|p| &p.ident)
+        .filter(|p| unused(p))
+        .collect();
+    let (ts_phantom_def, ts_phantom_init, ts_phantom_move) =
+        match phantom_lifetimes.is_empty() && phantom_params.is_empty() {
+            true => (None, None, None),
+            false => (
+                Some(quote::quote! {
+                    __phantom: std::marker::PhantomData<(
+                        #(&#phantom_lifetimes (),)*
+                        #(fn() -> #phantom_params,)*
+                    )>,
+                }),
+                Some(quote::quote! { __phantom: std::marker::PhantomData, }),
+                Some(quote::quote! { __phantom: self.__phantom, }),
+            ),
+        };
+
+    let ts_builder_def = stored.iter().zip(&stored_tys).map(|(f, ty)| {
+        let name = &f.ident;
+        quote::quote! { #name: #ty }
+    });
 
-    let ts_builder_init = fields.clone().map(|(name, _, _, attr)| match attr {
-        None => quote::quote! { #name: None },
-        Some(_) => quote::quote! { #name: Vec::new() },
+    let ts_builder_init = stored.iter().map(|f| {
+        let name = &f.ident;
+        if f.state.is_some() {
+            return quote::quote! { #name: () };
+        }
+        // `optional` is only meant for aliases of `Option`, so blame the
+        // field's type if it is something else
+        if f.optional && f.option.is_none() && f.each.is_none() {
+            let span = syn::spanned::Spanned::span(f.ty);
+            return quote::quote_spanned! {span=> #name: std::option::Option::None };
+        }
+        match f.each {
+            None => quote::quote! { #name: std::option::Option::None },
+            Some(_) => quote::quote! { #name: std::default::Default::default() },
+        }
+    });
+
+    let required = fields.iter().filter(|f| {
+        !f.optional
+            && f.each.is_none()
+            && f.default.is_none()
+            && f.sub_builder.is_none()
+            && !struct_attrs.default
+            && !struct_attrs.typestate
     });
 
-    let ts_build_extract = fields
+    let ts_error_variants = required.clone().map(|f| {
+        let variant = camel_ident("Missing", &f.ident);
+        let doc = format!("`{}` was not set.", syn::ext::IdentExt::unraw(&f.ident));
+        quote::quote! {
+            #[doc = #doc]
+            #variant
+        }
+    });
+
+    let ts_error_display = required.map(|f| {
+        let variant = camel_ident("Missing", &f.ident);
+        let msg = format!(
+            "field `{}` was not set",
+            syn::ext::IdentExt::unraw(&f.ident)
+        );
+        quote::quote! { Self::#variant => f.write_str(#msg) }
+    });
+
+    // errors returned by `check` and `validate` functions, and by the `build`
+    // of sub-builders, are boxed into a dedicated variant each
+    let checked = fields
+        .iter()
+        .filter(|f| f.check.is_some() || f.sub_builder.is_some());
+
+    let ts_error_invalid_variants = checked
+        .clone()
+        .map(|f| {
+            let variant = camel_ident("Invalid", &f.ident);
+            let doc = match f.sub_builder {
+                Some(_) => format!(
+                    "`{}` could not be built by its own builder.",
+                    syn::ext::IdentExt::unraw(&f.ident)
+                ),
+                None => format!(
+                    "`{}` was rejected by its check.",
+                    syn::ext::IdentExt::unraw(&f.ident)
+                ),
+            };
+            quote::quote! {
+                #[doc = #doc]
+                #variant(std::boxed::Box<dyn std::error::Error + Send + Sync>)
+            }
+        })
+        .chain(struct_attrs.validate.iter().map(|_| {
+            quote::quote! {
+                /// The builder was rejected by its validation function.
+                Invalid(std::boxed::Box<dyn std::error::Error + Send + Sync>)
+            }
+        }));
+
+    let ts_error_invalid_display = checked
         .clone()
-        .map(|(name, _, option, attr)| match (option, attr) {
-            (None, None) => quote::quote! { #name: self.#name.clone()? },
-            (Some(_), None) => quote::quote! { #name: self.#name.clone() },
-            (None, Some(_)) => quote::quote! { #name: self.#name.clone() },
-            _ => unimplemented!(),
+        .map(|f| {
+            let variant = camel_ident("Invalid", &f.ident);
+            let msg = format!(
+                "field `{}` is invalid: {{}}",
+                syn::ext::IdentExt::unraw(&f.ident)
+            );
+            (variant, msg)
+        })
+        .chain(struct_attrs.validate.iter().map(|_| {
+            (
+                quote::format_ident!("Invalid"),
+                format!("invalid {}: {{}}", input_ident),
+            )
+        }))
+        .map(|(variant, msg)| {
+            quote::quote! {
+                Self::#variant(ref err) => std::write!(f, #msg, err)
+            }
         });
 
-    let mut ts_builder_setters = quote::quote! {};
+    let ts_error_source = checked
+        .map(|f| camel_ident("Invalid", &f.ident))
+        .chain(
+            struct_attrs
+                .validate
+                .iter()
+                .map(|_| quote::format_ident!("Invalid")),
+        )
+        .map(|variant| {
+            quote::quote! {
+                Self::#variant(ref err) => std::option::Option::Some(&**err)
+            }
+        });
 
-    fields
-        .clone()
-        .for_each(|(name, ty, option, attr)| match (option, attr) {
-            (None, None) => ts_builder_setters.extend(quote::quote! {
-                pub fn #name(&mut self, value: #ty) -> &mut Self {
-                    self.#name = Some(value);
-                    self
+    let ts_build_default = if struct_attrs.default {
+        quote::quote! {
+            let __default: #input_ident #ty_generics = std::default::Default::default();
+        }
+    } else {
+        quote::quote! {}
+    };
+
+    let ts_build_validate = struct_attrs.validate.as_ref().map(|validate| {
+        quote::quote! {
+            #validate(&self)
+                .map_err(|err| #error_ident::Invalid(std::convert::Into::into(err)))?;
+        }
+    });
+
+    let ts_build_values = fields.iter().map(|f| {
+        let (member, name) = (&f.member, &f.ident);
+        let value = match struct_attrs.pattern {
+            Pattern::Owned => quote::quote! { self.#name },
+            Pattern::Mutable | Pattern::Immutable => quote::quote! { self.#name.clone() },
+        };
+        // from here on, lazy fields look like any other
+        let value = match (f.lazy, f.optional) {
+            (true, false) => quote::quote! { #value.map(|__lazy| __lazy()) },
+            (true, true) => quote::quote! { #value.and_then(|__lazy| __lazy()) },
+            (false, _) => value,
+        };
+        let default = match &f.default {
+            Some(FieldDefault::Trait) => Some(quote::quote! { std::default::Default::default() }),
+            Some(FieldDefault::Expr(expr)) => Some(quote::quote! { #expr }),
+            Some(FieldDefault::With(path)) => Some(quote::quote! { #path() }),
+            None => None,
+        };
+        let value = match (f.optional, &f.each, default) {
+            (_, _, Some(default)) if f.skip => default,
+            _ if f.skip => quote::quote! { __default.#member },
+            (optional, _, default) if struct_attrs.const_fn => {
+                // `Option`'s combinators are not `const`, so match by hand
+                let msg = format!("field `{}` was not set", syn::ext::IdentExt::unraw(name));
+                let none = match (optional, default) {
+                    (_, Some(default)) => default,
+                    (true, None) => quote::quote! { std::option::Option::None },
+                    (false, None) => quote::quote! { std::panic!(#msg) },
+                };
+                let some = if optional {
+                    quote::quote! { std::option::Option::Some(__value) }
+                } else {
+                    quote::quote! { __value }
+                };
+                quote::quote! {
+                    match #value {
+                        std::option::Option::Some(__value) => #some,
+                        std::option::Option::None => #none,
+                    }
+                }
+            }
+            (_, _, default) if f.sub_builder.is_some() => {
+                // an untouched sub-builder falls back to the defaults, or
+                // else to building a fresh one
+                let (ty, variant) = (f.ty, camel_ident("Invalid", name));
+                let build = |call: proc_macro2::TokenStream| {
+                    quote::quote! {
+                        #call.map_err(|err| {
+                            #error_ident::#variant(std::convert::Into::into(err))
+                        })?
+                    }
+                };
+                let fallback = match default {
+                    Some(default) => default,
+                    None if struct_attrs.default => quote::quote! { __default.#member },
+                    None => build(quote::quote! { <#ty>::builder().build() }),
+                };
+                // the accessor hands children out by `&mut`, so they have to
+                // build from a reference as well; blame the field's type for
+                // children that use the owned pattern
+                let (sub_builder, span) = (&f.sub_builder, syn::spanned::Spanned::span(ty));
+                let build_child = quote::quote_spanned! {span=>
+                    let __build: fn(&#sub_builder) -> _ = <#sub_builder>::build;
+                };
+                let build = build(quote::quote! { __build(__child) });
+                quote::quote! {
+                    match &self.#name {
+                        std::option::Option::Some(__child) => {
+                            #build_child
+                            #build
+                        }
+                        std::option::Option::None => #fallback,
+                    }
+                }
+            }
+            _ if f.state.is_some() => quote::quote! { #value.0 },
+            (false, None, Some(default)) => quote::quote! {
+                #value.unwrap_or_else(|| #default)
+            },
+            (false, None, None) if struct_attrs.default => quote::quote! {
+                #value.unwrap_or(__default.#member)
+            },
+            (false, None, None) => {
+                let variant = camel_ident("Missing", name);
+                quote::quote! {
+                    #value.ok_or(#error_ident::#variant)?
                 }
-            }),
+            }
+            (true, None, Some(default)) => quote::quote! {
+                #value.or_else(|| #default)
+            },
+            (true, None, None) if struct_attrs.default => quote::quote! {
+                #value.or(__default.#member)
+            },
+            (true, None, None) => quote::quote! { #value },
+            (false, Some(_), _) => quote::quote! { #value },
+            (true, Some(_), _) => {
+                unreachable!("`each` on `Option` fields is rejected while parsing")
+            }
+        };
+        let local = build_local(name);
+        let check = f.check.as_ref().map(|check| {
+            let variant = camel_ident("Invalid", name);
+            quote::quote! {
+                #check(&#local)
+                    .map_err(|err| #error_ident::#variant(std::convert::Into::into(err)))?;
+            }
+        });
+
+        quote::quote! {
+            let #local = #value;
+            #check
+        }
+    });
+
+    let ts_build_members = fields.iter().map(|f| {
+        let (member, local) = (&f.member, build_local(&f.ident));
+        quote::quote! { #member: #local }
+    });
 
-            (Some(ty), None) => ts_builder_setters.extend(quote::quote! {
-                pub fn #name(&mut self, value: #ty) -> &mut Self {
-                    self.#name = Some(value);
-                    self
+    // a borrowing `build` clones every field, which generic field types only
+    // support when the caller's type arguments do
+    let ts_build_bounds = stored
+        .iter()
+        .filter(|f| struct_attrs.pattern != Pattern::Owned && f.sub_builder.is_none())
+        .map(|f| match (f.option, &f.each) {
+            (Some(ty), None) => ty,
+            _ => f.ty,
+        })
+        .filter(|ty| mentions_type_param(ty, generics))
+        .map(|ty| quote::quote! { #ty: std::clone::Clone })
+        .chain(
+            fields
+                .iter()
+                .filter(|f| matches!(f.default, Some(FieldDefault::Trait)))
+                .filter(|f| mentions_type_param(f.ty, generics))
+                .map(|f| {
+                    let ty = f.ty;
+                    quote::quote! { #ty: std::default::Default }
+                }),
+        )
+        .chain(
+            (struct_attrs.default && generics.type_params().next().is_some())
+                .then(|| quote::quote! { #input_ident #ty_generics: std::default::Default }),
+        );
+
+    let ts_build_receiver = match struct_attrs.pattern {
+        Pattern::Owned => quote::quote! { self },
+        Pattern::Mutable | Pattern::Immutable => quote::quote! { &self },
+    };
+
+    let ts_const = struct_attrs.const_fn.then(|| quote::quote! { const });
+
+    // setter bodies operate on `__builder`, which is the builder itself or a
+    // copy of it depending on the pattern
+    let setter = |name: &syn::Ident,
+                  docs: &proc_macro2::TokenStream,
+                  params: proc_macro2::TokenStream,
+                  body: proc_macro2::TokenStream,
+                  bounds: Option<proc_macro2::TokenStream>| {
+        let bounds = bounds.into_iter();
+        match struct_attrs.pattern {
+            Pattern::Mutable => quote::quote! {
+                #docs
+                pub fn #name(&mut self, #params) -> &mut Self
+                where
+                    #(#bounds,)*
+                {
+                    let __builder = self;
+                    #body;
+                    __builder
                 }
-            }),
+            },
+            Pattern::Owned => quote::quote! {
+                #docs
+                pub #ts_const fn #name(self, #params) -> Self
+                where
+                    #(#bounds,)*
+                {
+                    let mut __builder = self;
+                    #body;
+                    __builder
+                }
+            },
+            Pattern::Immutable => quote::quote! {
+                #docs
+                pub fn #name(&self, #params) -> Self
+                where
+                    Self: std::clone::Clone,
+                    #(#bounds,)*
+                {
+                    let mut __builder = std::clone::Clone::clone(self);
+                    #body;
+                    __builder
+                }
+            },
+        }
+    };
 
-            (None, Some((arg_name, arg_ty))) => {
+    // a `try_` setter converts its argument and hands it on to the normal
+    // setter, so it returns whatever that returns
+    let try_setter = |name: &syn::Ident,
+                      docs: &proc_macro2::TokenStream,
+                      ty: &syn::Type,
+                      receiver: proc_macro2::TokenStream,
+                      ret: proc_macro2::TokenStream| {
+        let try_name = quote::format_ident!("try_{}", syn::ext::IdentExt::unraw(name));
+        let bounds = (struct_attrs.pattern == Pattern::Immutable)
+            .then(|| quote::quote! { Self: std::clone::Clone });
+        let doc = format!(
+            "Fallible version of [`{}`](Self::{0}): the value is converted with `TryInto` \
+             first, and the conversion error returned if there is one.",
+            name
+        );
+        quote::quote! {
+            #docs
+            #[doc = #doc]
+            pub fn #try_name<__V>(#receiver, value: __V) -> std::result::Result<
+                #ret,
+                <__V as std::convert::TryInto<#ty>>::Error,
+            >
+            where
+                __V: std::convert::TryInto<#ty>,
+                #bounds
+            {
+                let value: #ty = std::convert::TryInto::try_into(value)?;
+                std::result::Result::Ok(self.#name(value))
+            }
+        }
+    };
+    let (try_receiver, try_ret) = match struct_attrs.pattern {
+        Pattern::Mutable => (quote::quote! { &mut self }, quote::quote! { &mut Self }),
+        Pattern::Owned => (quote::quote! { self }, quote::quote! { Self }),
+        Pattern::Immutable => (quote::quote! { &self }, quote::quote! { Self }),
+    };
+
+    let mut ts_builder_setters = quote::quote! {};
+    let mut ts_state_setters = quote::quote! {};
+
+    stored.iter().for_each(|f| {
+        let (name, ty) = (&f.ident, f.ty);
+
+        // the field's own docs, followed by whether it has to be set
+        let status = match (&f.default, &f.each) {
+            _ if f.state.is_some() => {
+                "Required: `build` is only available once this was called.".to_owned()
+            }
+            _ if f.sub_builder.is_some() => {
+                "Returns the field's own builder, creating it on first use.".to_owned()
+            }
+            (Some(FieldDefault::Trait), _) => {
+                "Optional: defaults to `Default::default()`.".to_owned()
+            }
+            (Some(FieldDefault::Expr(expr)), _) => format!(
+                "Optional: defaults to `{}`.",
+                quote::ToTokens::to_token_stream(expr)
+            ),
+            (Some(FieldDefault::With(path)), _) => format!(
+                "Optional: defaults to the result of `{}()`, which is only called if this \
+                 never is.",
+                quote::ToTokens::to_token_stream(path)
+            ),
+            _ if struct_attrs.default => {
+                "Optional: defaults to the struct's `Default` value.".to_owned()
+            }
+            (None, Some(_)) => "Optional: starts out empty.".to_owned(),
+            _ if f.optional => "Optional: left `None` if never called.".to_owned(),
+            _ => "Required: `build` fails if this is never called.".to_owned(),
+        };
+        let field_docs = &f.docs;
+        let separator = (!f.docs.is_empty()).then(|| quote::quote! { #[doc = ""] });
+        let docs = quote::quote! {
+            #(#field_docs)*
+            #separator
+            #[doc = #status]
+        };
+
+        // with `setter(into)` the arguments are converted before `body` sees
+        // them
+        let args = |args: &[(&str, &syn::Type)], body: proc_macro2::TokenStream| {
+            let idents = args.iter().map(|(arg, _)| quote::format_ident!("{}", arg));
+            let tys = args.iter().map(|(_, ty)| ty);
+            if f.into {
+                let (idents, tys) = (idents.collect::<Vec<_>>(), tys.collect::<Vec<_>>());
+                (
+                    quote::quote! { #(#idents: impl std::convert::Into<#tys>),* },
+                    quote::quote! {
+                        #(let #idents: #tys = std::convert::Into::into(#idents);)*
+                        #body
+                    },
+                )
+            } else {
+                (quote::quote! { #(#idents: #tys),* }, body)
+            }
+        };
+
+        match (f.optional, f.option, &f.each) {
+            _ if f.sub_builder.is_some() => {
+                // hands out the nested builder, creating it on first use
+                let sub_builder = &f.sub_builder;
                 ts_builder_setters.extend(quote::quote! {
-                    pub fn #arg_name(&mut self, value: #arg_ty) -> &mut Self {
-                        self.#name.push(value);
-                        self
+                    #docs
+                    pub fn #name(&mut self) -> &mut #sub_builder {
+                        self.#name.get_or_insert_with(<#ty>::builder)
+                    }
+                });
+            }
+
+            _ if f.lazy => {
+                let (params, body) = (
+                    quote::quote! { value: impl std::ops::FnOnce() -> #ty + 'static },
+                    quote::quote! {
+                        __builder.#name = std::option::Option::Some(std::boxed::Box::new(value))
+                    },
+                );
+                ts_builder_setters.extend(setter(name, &docs, params, body, None));
+            }
+
+            (false, _, None) if f.state.is_some() => {
+                // the setter moves every field into a builder whose state
+                // parameter for this field is set
+                let others = stored
+                    .iter()
+                    .filter(|other| other.ident != *name)
+                    .map(|other| &other.ident);
+                let (params, body) = args(
+                    &[("value", ty)],
+                    quote::quote! {
+                        #builder_ident {
+                            #name: (value,),
+                            #(#others: self.#others,)*
+                            #ts_phantom_move
+                        }
+                    },
+                );
+
+                // whatever the field's state was before, so that builders
+                // made by `to_builder` can change already set fields
+                let mut impl_generics = generics.clone();
+                impl_generics.params.extend(
+                    fields
+                        .iter()
+                        .filter_map(|other| other.state.as_ref())
+                        .map(|state| -> syn::GenericParam { syn::parse_quote! { #state } }),
+                );
+                let (impl_generics, _, _) = impl_generics.split_for_impl();
+                let states = |set: proc_macro2::TokenStream| {
+                    fields
+                        .iter()
+                        .filter_map(|other| {
+                            other.state.as_ref().map(|state| {
+                                if other.ident == *name {
+                                    set.clone()
+                                } else {
+                                    quote::quote! { #state }
+                                }
+                            })
+                        })
+                        .collect::<Vec<_>>()
+                };
+                let state = &f.state;
+                let (current, set) = (
+                    states(quote::quote! { #state }),
+                    states(quote::quote! { (#ty,) }),
+                );
+                let ret = quote::quote! { #builder_ident<#(#generic_args,)* #(#set,)*> };
+                let ts_try_setter = f
+                    .try_setter
+                    .then(|| try_setter(name, &docs, ty, quote::quote! { self }, ret.clone()));
+
+                ts_state_setters.extend(quote::quote! {
+                    impl #impl_generics #builder_ident<#(#generic_args,)* #(#current,)*> #where_clause {
+                        #docs
+                        pub fn #name(self, #params) -> #ret {
+                            #body
+                        }
+
+                        #ts_try_setter
                     }
                 });
+            }
+
+            (false, _, None) => {
+                let (params, body) = args(
+                    &[("value", ty)],
+                    quote::quote! { __builder.#name = std::option::Option::Some(value) },
+                );
+                ts_builder_setters.extend(setter(name, &docs, params, body, None));
+            }
+
+            (true, Some(ty), None) if f.strip_option => {
+                let (params, body) = args(
+                    &[("value", ty)],
+                    quote::quote! { __builder.#name = std::option::Option::Some(value) },
+                );
+                ts_builder_setters.extend(setter(name, &docs, params, body, None));
+            }
+
+            (true, _, None) => {
+                let (params, body) =
+                    args(&[("value", ty)], quote::quote! { __builder.#name = value });
+                ts_builder_setters.extend(setter(name, &docs, params, body, None));
+            }
 
-                if *name != arg_name {
-                    ts_builder_setters.extend(quote::quote! {
-                        pub fn #name(&mut self, value: #ty) -> &mut Self {
-                            self.#name = value;
-                            self
+            (false, _, Some(each)) => {
+                let (params, item, item_ty) = match each.key {
+                    Some(key) => {
+                        let value = each.value;
+                        let (params, body) = args(
+                            &[("key", key), ("value", value)],
+                            quote::quote! { (key, value) },
+                        );
+                        (params, body, quote::quote! { (#key, #value) })
+                    }
+                    None => {
+                        let value = each.value;
+                        let (params, body) = args(&[("value", value)], quote::quote! { value });
+                        (params, body, quote::quote! { #value })
+                    }
+                };
+                // only generic collections can fail to implement `Extend`
+                let bounds = mentions_type_param(ty, generics)
+                    .then(|| quote::quote! { #ty: std::iter::Extend<#item_ty> });
+                let body = quote::quote! {
+                    let item = { #item };
+                    std::iter::Extend::extend(
+                        &mut __builder.#name,
+                        std::option::Option::Some(item),
+                    )
+                };
+                let each_docs = quote::quote! {
+                    #(#field_docs)*
+                    #separator
+                    #[doc = "Adds a single element to the collection."]
+                };
+                ts_builder_setters.extend(setter(&each.ident, &each_docs, params, body, bounds));
+
+                if *name != each.ident {
+                    let (params, body) =
+                        args(&[("value", ty)], quote::quote! { __builder.#name = value });
+                    ts_builder_setters.extend(setter(name, &docs, params, body, None));
+                }
+            }
+
+            (true, _, Some(_)) => {
+                unreachable!("`each` on `Option` fields is rejected while parsing")
+            }
+        }
+
+        // typestate fields got theirs next to the state transition
+        if f.try_setter && f.state.is_none() {
+            let ty = match f.option {
+                Some(ty) if f.strip_option => ty,
+                _ => ty,
+            };
+            ts_builder_setters.extend(try_setter(
+                name,
+                &docs,
+                ty,
+                try_receiver.clone(),
+                try_ret.clone(),
+            ));
+        }
+    });
+
+    // collections of `each` fields start out empty
+    let ts_builder_bounds = stored
+        .iter()
+        .filter(|f| f.each.is_some() && mentions_type_param(f.ty, generics))
+        .map(|f| {
+            let ty = f.ty;
+            quote::quote! { #ty: std::default::Default }
+        });
+
+    // a typestate builder can only be built once every state parameter is set
+    let ts_build_self_ty = if struct_attrs.typestate {
+        let states = fields.iter().filter(|f| f.state.is_some()).map(|f| f.ty);
+        quote::quote! { #builder_ident<#(#generic_args,)* #((#states,),)*> }
+    } else {
+        quote::quote! { #builder_ident #ty_generics }
+    };
+
+    // with nothing left to go wrong at runtime, a typestate builder returns
+    // the struct itself
+    let fallible = !(struct_attrs.typestate || struct_attrs.const_fn)
+        || fields
+            .iter()
+            .any(|f| f.check.is_some() || f.sub_builder.is_some());
+
+    let ts_build_ret = if fallible {
+        quote::quote! { std::result::Result<#input_ident #ty_generics, #error_ident> }
+    } else {
+        quote::quote! { #input_ident #ty_generics }
+    };
+
+    let ts_build_struct = quote::quote! {
+        #input_ident {
+            #(
+                #ts_build_members,
+            )*
+        }
+    };
+    let ts_build_struct = if fallible {
+        quote::quote! { std::result::Result::Ok(#ts_build_struct) }
+    } else {
+        ts_build_struct
+    };
+
+    let build_doc = if fallible {
+        format!(
+            "Builds the [`{}`], or fails if a required field was not set or a value \
+             was rejected.",
+            input_ident
+        )
+    } else if struct_attrs.const_fn {
+        format!(
+            "Builds the [`{}`], panicking if a required field was not set.",
+            input_ident
+        )
+    } else {
+        format!("Builds the [`{}`].", input_ident)
+    };
+
+    let ts_error = fallible.then(|| {
+        let doc = format!("Error returned by [`{}::build`].", builder_ident);
+        quote::quote! {
+            #[doc = #doc]
+            #[derive(Debug)]
+            #vis enum #error_ident {
+                #(
+                    #ts_error_variants,
+                )*
+                #(
+                    #ts_error_invalid_variants,
+                )*
+            }
+
+            impl std::fmt::Display for #error_ident {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    match *self {
+                        #(
+                            #ts_error_display,
+                        )*
+                        #(
+                            #ts_error_invalid_display,
+                        )*
+                    }
+                }
+            }
+
+            impl std::error::Error for #error_ident {
+                fn source(&self) -> std::option::Option<&(dyn std::error::Error + 'static)> {
+                    #[allow(unreachable_patterns)]
+                    match *self {
+                        #(
+                            #ts_error_source,
+                        )*
+                        _ => std::option::Option::None,
+                    }
+                }
+            }
+        }
+    });
+
+    // turning a finished struct back into a builder sets every stored field
+    let ts_from_fields = stored.iter().map(|f| {
+        let (member, name) = (&f.member, &f.ident);
+        match (f.optional, &f.each) {
+            _ if f.state.is_some() => quote::quote! { #name: (value.#member,) },
+            _ if f.sub_builder.is_some() => quote::quote! {
+                #name: std::option::Option::Some(std::convert::From::from(value.#member))
+            },
+            _ if f.lazy => quote::quote! {
+                #name: {
+                    let __value = value.#member;
+                    std::option::Option::Some(std::boxed::Box::new(move || __value))
+                }
+            },
+            (false, None) => quote::quote! {
+                #name: std::option::Option::Some(value.#member)
+            },
+            _ => quote::quote! { #name: value.#member },
+        }
+    });
+
+    // with the `serde` feature builders can be read from partial layers of
+    // configuration and merged before building; typestate builders change
+    // type with every required field, so they are left out
+    let serde = cfg!(feature = "serde") && !struct_attrs.typestate;
+    // serde only borrows from the input for `&'de` references and rejects
+    // bounds naming `'de` otherwise, so builders holding references are
+    // merge-only
+    let deserialize = serde && !stored.iter().any(|f| mentions_reference(f.ty));
+
+    let ts_serde_attrs = stored.iter().map(|f| {
+        deserialize.then(|| match f.lazy {
+            true => quote::quote! { #[serde(skip)] },
+            false => quote::quote! { #[serde(default)] },
+        })
+    });
+    let ts_serde_skip = deserialize.then(|| quote::quote! { #[serde(skip)] });
+    let ts_phantom_def = ts_phantom_def.map(|def| quote::quote! { #ts_serde_skip #def });
+
+    // bounding every stored type instead of the type parameters keeps
+    // builders with fields that cannot be deserialized compiling, they just
+    // do not implement `Deserialize`
+    let ts_serde_bound = deserialize.then(|| {
+        let bound = stored
+            .iter()
+            .zip(&stored_tys)
+            .filter(|(f, _)| !f.lazy)
+            .map(|(_, ty)| quote::quote! { #ty: serde::Deserialize<'de> }.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        quote::quote! { #[serde(bound(deserialize = #bound))] }
+    });
+
+    let ts_merge = serde.then(|| {
+        // later layers replace values that were set and append to
+        // collections
+        let merges = stored.iter().map(|f| {
+            let name = &f.ident;
+            if f.sub_builder.is_some() {
+                quote::quote! {
+                    match (&mut self.#name, other.#name) {
+                        (std::option::Option::Some(builder), std::option::Option::Some(other)) => {
+                            builder.merge(other)
                         }
-                    });
+                        (builder, other @ std::option::Option::Some(_)) => *builder = other,
+                        (_, std::option::Option::None) => {}
+                    }
+                }
+            } else if f.each.is_some() {
+                quote::quote! { std::iter::Extend::extend(&mut self.#name, other.#name) }
+            } else {
+                // like in `builder()`, blame `optional` on a non-`Option`
+                let span = syn::spanned::Spanned::span(f.ty);
+                quote::quote_spanned! {span=>
+                    if std::option::Option::is_some(&other.#name) {
+                        self.#name = other.#name;
+                    }
                 }
             }
+        });
+        let bounds = stored
+            .iter()
+            .filter(|f| f.each.is_some() && mentions_type_param(f.ty, generics))
+            .map(|f| {
+                let ty = f.ty;
+                quote::quote! {
+                    #ty: std::iter::IntoIterator
+                        + std::iter::Extend<<#ty as std::iter::IntoIterator>::Item>
+                }
+            });
 
-            _ => unimplemented!(),
+        quote::quote! {
+            /// Lays `other` over `self`: fields set in `other` replace those
+            /// of `self` and collections are appended to.
+            pub fn merge(&mut self, other: Self)
+            where
+                #(#bounds,)*
+            {
+                #(
+                    #merges;
+                )*
+            }
+        }
+    });
+
+    let builder_doc = match &struct_attrs.doc {
+        Some(doc) => doc.value(),
+        None => format!("Builder for [`{}`].", input_ident),
+    };
+
+    let mut builder_derives: Vec<syn::Path> = vec![syn::parse_quote! { Debug }];
+    if struct_attrs.pattern == Pattern::Immutable {
+        builder_derives.push(syn::parse_quote! { Clone });
+    }
+    if deserialize {
+        builder_derives.push(syn::parse_quote! { serde::Deserialize });
+    }
+    let name = |path: &syn::Path| path.segments.last().map(|s| s.ident.clone());
+    for derive in &struct_attrs.derive {
+        if !builder_derives.iter().any(|d| name(d) == name(derive)) {
+            builder_derives.push(derive.clone());
+        }
+    }
+
+    // closures have no `Debug` output, so builders with lazy fields print a
+    // placeholder for them through a handwritten impl
+    let lazy = stored.iter().any(|f| f.lazy);
+    if lazy {
+        builder_derives.retain(|d| name(d) != Some(quote::format_ident!("Debug")));
+    }
+
+    let ts_debug = lazy.then(|| {
+        let debug_name = builder_ident.to_string();
+        let ts_debug_fields = stored.iter().map(|f| {
+            let name = &f.ident;
+            let label = syn::ext::IdentExt::unraw(name).to_string();
+            if f.lazy {
+                quote::quote! { .field(#label, &self.#name.as_ref().map(|_| __Lazy)) }
+            } else {
+                quote::quote! { .field(#label, &self.#name) }
+            }
         });
 
+        let mut debug_generics = builder_generics.clone();
+        debug_generics.make_where_clause().predicates.extend(
+            stored
+                .iter()
+                .zip(&stored_tys)
+                .filter(|(f, _)| !f.lazy && mentions_type_param(f.ty, generics))
+                .map(|(_, ty)| -> syn::WherePredicate {
+                    syn::parse_quote! { #ty: std::fmt::Debug }
+                }),
+        );
+        let (_, _, debug_where_clause) = debug_generics.split_for_impl();
+
+        quote::quote! {
+            impl #builder_impl_generics std::fmt::Debug for #builder_ident #builder_ty_generics
+            #debug_where_clause
+            {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    struct __Lazy;
+
+                    impl std::fmt::Debug for __Lazy {
+                        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                            f.write_str("<lazy>")
+                        }
+                    }
+
+                    f.debug_struct(#debug_name)
+                        #(#ts_debug_fields)*
+                        .finish()
+                }
+            }
+        }
+    });
+
+    // boxing the value of a lazy field into a closure needs it to be `'static`
+    let lazy_bounds: Vec<syn::WherePredicate> = stored
+        .iter()
+        .filter(|f| f.lazy && mentions_type_param(f.ty, generics))
+        .map(|f| {
+            let ty = f.ty;
+            syn::parse_quote! { #ty: 'static }
+        })
+        .collect();
+    let mut from_generics = input.generics.clone();
+    from_generics
+        .make_where_clause()
+        .predicates
+        .extend(lazy_bounds.iter().cloned());
+    let (_, _, from_where_clause) = from_generics.split_for_impl();
+
     // combine all token-streams
     // -------------------------
-    let ts_expanded = quote::quote! {
-        impl #input_ident {
-            fn builder() -> #builder_ident {
+    Ok(quote::quote! {
+        impl #impl_generics #input_ident #ty_generics #where_clause {
+            /// Creates a builder with no field set yet.
+            #vis #ts_const fn builder() -> #builder_ident #ty_generics
+            where
+                #(#ts_builder_bounds,)*
+            {
                 #builder_ident {
                     #(
                         #ts_builder_init,
                     )*
+                    #ts_phantom_init
+                }
+            }
+
+            /// Creates a builder with every field set to its value in `self`.
+            #vis fn to_builder(&self) -> #ts_build_self_ty
+            where
+                // a plain `Self: Clone` is rejected as a trivially false bound
+                // for structs that aren't `Clone`; quantifying over an unused
+                // lifetime defers the check to callers of `to_builder`
+                for<'__a> Self: std::clone::Clone,
+                #(#lazy_bounds,)*
+            {
+                std::convert::From::from(std::clone::Clone::clone(self))
+            }
+        }
+
+        impl #impl_generics std::convert::From<#input_ident #ty_generics> for #ts_build_self_ty
+        #from_where_clause
+        {
+            fn from(value: #input_ident #ty_generics) -> Self {
+                #builder_ident {
+                    #(
+                        #ts_from_fields,
+                    )*
+                    #ts_phantom_init
                 }
             }
         }
 
-        #[derive(Debug)]
-        struct #builder_ident {
+        #[doc = #builder_doc]
+        #[derive(#(#builder_derives),*)]
+        #ts_serde_bound
+        #vis struct #builder_ident #builder_generics #where_clause {
             #(
+                #ts_serde_attrs
                 #ts_builder_def,
             )*
+            #ts_phantom_def
         }
 
-        impl #builder_ident {
-            pub fn build(&self) -> std::option::Option<#input_ident> {
-                Some(#input_ident {
-                    #(
-                        #ts_build_extract,
-                    )*
-                })
+        #ts_debug
+
+        #ts_error
+
+        impl #impl_generics #ts_build_self_ty #where_clause {
+            #[doc = #build_doc]
+            pub #ts_const fn build(#ts_build_receiver) -> #ts_build_ret
+            where
+                #(#ts_build_bounds,)*
+            {
+                #ts_build_validate
+                #ts_build_default
+
+                #(
+                    #ts_build_values
+                )*
+
+                #ts_build_struct
             }
+        }
 
+        impl #builder_impl_generics #builder_ident #builder_ty_generics #where_clause {
             #ts_builder_setters
+
+            #ts_merge
         }
-    };
 
-    proc_macro::TokenStream::from(ts_expanded)
+        #ts_state_setters
+    })
+}
+
+/// The generic arguments matching the parameters declared in `generics`, e.g.
+/// `'a, T, N` for `<'a, T: Clone, const N: usize>`.
+fn generic_args(generics: &syn::Generics) -> Vec<proc_macro2::TokenStream> {
+    generics
+        .params
+        .iter()
+        .map(|param| match param {
+            syn::GenericParam::Lifetime(l) => quote::ToTokens::to_token_stream(&l.lifetime),
+            syn::GenericParam::Type(t) => quote::ToTokens::to_token_stream(&t.ident),
+            syn::GenericParam::Const(c) => quote::ToTokens::to_token_stream(&c.ident),
+        })
+        .collect()
+}
+
+/// The local `build` holds the value of `field` in. Named apart from the
+/// field, so it can't shadow the paths given to `check`, `default` and
+/// `default_with`.
+fn build_local(field: &syn::Ident) -> syn::Ident {
+    quote::format_ident!("__field_{}", syn::ext::IdentExt::unraw(field))
 }
 
-fn inner_ty<'a>(ty: &'a syn::Type, outer_ty: &str) -> Option<&'a syn::Type> {
-    if let syn::Type::Path(syn::TypePath {
-        path: syn::Path { segments, .. },
-        ..
-    }) = ty
-    {
-        let segment = &segments[0];
+/// `field` in UpperCamelCase behind `prefix`, e.g. `current_dir` with prefix
+/// `Missing` -> `MissingCurrentDir`.
+fn camel_ident(prefix: &str, field: &syn::Ident) -> syn::Ident {
+    let camel: String = syn::ext::IdentExt::unraw(field)
+        .to_string()
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|c| c.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect();
 
-        if segment.ident == outer_ty {
-            if let syn::PathArguments::AngleBracketed(generic) = &segment.arguments {
-                if let syn::GenericArgument::Type(ty) = generic.args.first().unwrap() {
-                    return Some(ty);
-                }
+    quote::format_ident!("{}{}", prefix, camel)
+}
+
+/// Whether any of the type parameters declared in `generics` occurs in `ty`.
+fn mentions_type_param(ty: &syn::Type, generics: &syn::Generics) -> bool {
+    fn walk(tokens: proc_macro2::TokenStream, generics: &syn::Generics) -> bool {
+        tokens.into_iter().any(|token| match token {
+            proc_macro2::TokenTree::Ident(ident) => {
+                generics.type_params().any(|param| param.ident == ident)
             }
-        }
+            proc_macro2::TokenTree::Group(group) => walk(group.stream(), generics),
+            _ => false,
+        })
     }
 
-    None
+    walk(quote::ToTokens::to_token_stream(ty), generics)
 }
 
-fn inner_for_attr(field: &syn::Field) -> Option<(syn::Ident, &syn::Type)> {
-    if let Some(attr) = field.attrs.first() {
-        if let Ok(syn::Meta::List(meta)) = attr.parse_meta() {
-            if let Some(seg) = meta.path.segments.first() {
-                if seg.ident == "builder" {
-                    if let Some(syn::NestedMeta::Meta(syn::Meta::NameValue(nested))) =
-                        meta.nested.first()
-                    {
-                        if let Some(nested_seg) = nested.path.segments.first() {
-                            if nested_seg.ident == "each" {
-                                if let syn::Lit::Str(lit) = &nested.lit {
-                                    return Some((
-                                        syn::Ident::new(
-                                            &lit.value(),
-                                            proc_macro2::Span::call_site(),
-                                        ),
-                                        inner_ty(&field.ty, "Vec").unwrap(),
-                                    ));
-                                }
-                            }
-                        }
-                    }
+/// Whether `tokens` contain `ident` anywhere, e.g. a type parameter or the
+/// name of a lifetime.
+fn mentions_ident(tokens: proc_macro2::TokenStream, ident: &syn::Ident) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(other) => other == *ident,
+        proc_macro2::TokenTree::Group(group) => mentions_ident(group.stream(), ident),
+        _ => false,
+    })
+}
+
+/// Whether `ty` names one of the lifetimes declared in `generics`.
+fn mentions_lifetime(ty: &syn::Type, generics: &syn::Generics) -> bool {
+    fn walk(tokens: proc_macro2::TokenStream, generics: &syn::Generics) -> bool {
+        let mut tick = false;
+        tokens.into_iter().any(|token| {
+            let after_tick = std::mem::replace(&mut tick, false);
+            match token {
+                proc_macro2::TokenTree::Punct(punct) => {
+                    tick = punct.as_char() == '\'';
+                    false
+                }
+                proc_macro2::TokenTree::Ident(ident) => {
+                    after_tick && generics.lifetimes().any(|l| l.lifetime.ident == ident)
                 }
+                proc_macro2::TokenTree::Group(group) => walk(group.stream(), generics),
+                proc_macro2::TokenTree::Literal(_) => false,
+            }
+        })
+    }
+
+    walk(quote::ToTokens::to_token_stream(ty), generics)
+}
+
+/// Whether `ty` contains a reference anywhere, e.g. `Option<&'static str>`.
+fn mentions_reference(ty: &syn::Type) -> bool {
+    fn walk(tokens: proc_macro2::TokenStream) -> bool {
+        tokens.into_iter().any(|token| match token {
+            proc_macro2::TokenTree::Punct(punct) => punct.as_char() == '&',
+            proc_macro2::TokenTree::Group(group) => walk(group.stream()),
+            _ => false,
+        })
+    }
+
+    walk(quote::ToTokens::to_token_stream(ty))
+}
+
+/// Type arguments of the last path segment of `ty`, e.g. `[K, V]` for
+/// `std::collections::BTreeMap<K, V>`.
+fn collection_item_tys(ty: &syn::Type) -> Vec<&syn::Type> {
+    match ty {
+        syn::Type::Path(syn::TypePath { qself: None, path }) => match path.segments.last() {
+            Some(syn::PathSegment {
+                arguments: syn::PathArguments::AngleBracketed(generic),
+                ..
+            }) => generic
+                .args
+                .iter()
+                .filter_map(|arg| match arg {
+                    syn::GenericArgument::Type(ty) => Some(ty),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+/// Whether `ty` looks like a map, whose `each` setter takes a key and a value.
+fn is_map(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(syn::TypePath { path, .. }) => path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident.to_string().ends_with("Map")),
+        _ => false,
+    }
+}
+
+/// Whether `ty` certainly has no destructor, so that `const fn`s may
+/// overwrite and move its values.
+fn is_const_safe(ty: &syn::Type) -> bool {
+    const PRIMITIVES: &[&str] = &[
+        "bool", "char", "f32", "f64", "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16",
+        "u32", "u64", "u128", "usize",
+    ];
+
+    match ty {
+        syn::Type::Reference(_) => true,
+        syn::Type::Array(array) => is_const_safe(&array.elem),
+        syn::Type::Tuple(tuple) => tuple.elems.iter().all(is_const_safe),
+        syn::Type::Paren(paren) => is_const_safe(&paren.elem),
+        syn::Type::Path(path) => match option_inner_ty(ty) {
+            Some(inner) => is_const_safe(inner),
+            None => path
+                .path
+                .get_ident()
+                .is_some_and(|ident| PRIMITIVES.contains(&ident.to_string().as_str())),
+        },
+        _ => false,
+    }
+}
+
+/// The `T` of `Option<T>`, written either as `Option<T>` or through its full
+/// path in `std` or `core`. Other paths ending in `Option` are user types.
+fn option_inner_ty(ty: &syn::Type) -> Option<&syn::Type> {
+    let path = match ty {
+        syn::Type::Path(syn::TypePath { qself: None, path }) => path,
+        _ => return None,
+    };
+
+    let segments: Vec<_> = path.segments.iter().collect();
+    let (last, init) = segments.split_last()?;
+    let idents: Vec<String> = segments.iter().map(|s| s.ident.to_string()).collect();
+    let is_option = match idents.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["Option"] => path.leading_colon.is_none(),
+        ["std" | "core", "option", "Option"] => true,
+        _ => false,
+    };
+    if !is_option || init.iter().any(|s| !s.arguments.is_empty()) {
+        return None;
+    }
+
+    match &last.arguments {
+        syn::PathArguments::AngleBracketed(generic) if generic.args.len() == 1 => {
+            match generic.args.first() {
+                Some(syn::GenericArgument::Type(ty)) => Some(ty),
+                _ => None,
             }
         }
+        _ => None,
     }
-    None
 }
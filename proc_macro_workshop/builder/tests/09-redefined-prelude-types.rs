@@ -13,6 +13,9 @@
 // Generally all macros (procedural as well as macro_rules) designed to be used
 // by other people should refer to every single thing in their expanded code
 // through an absolute path, such as std::result::Result.
+//
+// Redefining the names as types does not affect expressions and patterns, so
+// the variants of `Shadow` are glob-imported to shadow the values as well.
 
 use derive_builder::Builder;
 
@@ -22,9 +25,23 @@ type None = ();
 type Result = ();
 type Box = ();
 
+#[allow(dead_code)]
+pub enum Shadow {
+    None,
+    Some(()),
+    Ok(()),
+    Err(()),
+}
+
+#[allow(unused_imports)]
+use Shadow::*;
+
 #[derive(Builder)]
 pub struct Command {
     executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: std::option::Option<String>,
 }
 
 fn main() {}
@@ -0,0 +1,47 @@
+// Instead of a bare `Option`, `build` reports which required field is missing
+// through a generated `{Struct}BuildError` enum. It has one variant per
+// required field and implements `std::error::Error` and `Display`, so it can
+// be propagated with `?` into a `Box<dyn Error>`.
+//
+//     impl CommandBuilder {
+//         pub fn build(&self) -> Result<Command, CommandBuildError> {
+//             ...
+//         }
+//     }
+
+use derive_builder::Builder;
+use std::error::Error;
+
+#[derive(Debug, Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: String,
+    timeout: Option<u64>,
+}
+
+fn load() -> Result<Command, Box<dyn Error>> {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .current_dir("..".to_owned())
+        .build()?;
+    Ok(command)
+}
+
+fn main() {
+    let err = Command::builder()
+        .executable("cargo".to_owned())
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, CommandBuildError::MissingCurrentDir));
+    assert_eq!(err.to_string(), "field `current_dir` was not set");
+
+    let err = Command::builder().build().unwrap_err();
+    assert!(matches!(err, CommandBuildError::MissingExecutable));
+
+    let command = load().unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert!(command.args.is_empty());
+    assert!(command.timeout.is_none());
+}
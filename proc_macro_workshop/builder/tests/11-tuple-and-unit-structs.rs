@@ -0,0 +1,32 @@
+// Tuple structs get positional setters named after the field index (`_0`,
+// `_1`, ...) unless a field picks a nicer name through
+// #[builder(name = "...")]. Unit structs get a builder with nothing to set.
+//
+//     impl AddressBuilder {
+//         pub fn host(&mut self, value: String) -> &mut Self { ... }
+//         pub fn _1(&mut self, value: u16) -> &mut Self { ... }
+//     }
+
+use derive_builder::Builder;
+
+#[derive(Debug, Builder)]
+pub struct Address(#[builder(name = "host")] String, u16, Option<String>);
+
+#[derive(Debug, Builder)]
+pub struct Marker;
+
+fn main() {
+    let address = Address::builder()
+        .host("localhost".to_owned())
+        ._1(8080)
+        .build()
+        .unwrap();
+    assert_eq!(address.0, "localhost");
+    assert_eq!(address.1, 8080);
+    assert!(address.2.is_none());
+
+    let err = Address::builder().host("localhost".to_owned()).build().unwrap_err();
+    assert!(matches!(err, AddressBuildError::Missing1));
+
+    let _marker: Marker = Marker::builder().build().unwrap();
+}
@@ -0,0 +1,13 @@
+// Builders only make sense for structs. Deriving one for an enum is reported
+// as a regular compile error pointing at the `enum` keyword instead of a
+// panic inside the macro.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub enum Shape {
+    Circle(f64),
+    Square(f64),
+}
+
+fn main() {}
@@ -0,0 +1,79 @@
+// Generic parameters, lifetimes and where-clauses of the input struct are
+// carried over to the builder, its impl and the `builder()` constructor.
+//
+//     struct ConnBuilder<'a, T: Transport> where T: Clone { ... }
+//
+//     impl<'a, T: Transport> ConnBuilder<'a, T> where T: Clone {
+//         pub fn build(&self) -> Result<Conn<'a, T>, ConnBuildError> { ... }
+//     }
+//
+// Type parameters and lifetimes the builder doesn't store anywhere, like
+// those of skipped fields, still need to be used by the builder, which is
+// what a PhantomData marker field is for. Builders using all of them don't
+// get the marker, so it never shows up in their Debug output.
+
+use derive_builder::Builder;
+
+pub trait Transport {
+    type Addr;
+}
+
+#[derive(Debug, Clone)]
+pub struct Tcp;
+
+impl Transport for Tcp {
+    type Addr = String;
+}
+
+#[derive(Builder)]
+pub struct Conn<'a, T: Transport>
+where
+    T: Clone,
+    T::Addr: Clone,
+{
+    name: &'a str,
+    transport: T,
+    #[builder(each = "peer")]
+    peers: Vec<T::Addr>,
+}
+
+#[derive(Builder)]
+pub struct Cache<K, V> {
+    key: Option<K>,
+    #[builder(each = "value")]
+    values: Vec<V>,
+}
+
+#[derive(Builder)]
+pub struct Tagged<'a, T> {
+    id: u32,
+    #[builder(skip)]
+    tag: Option<&'a T>,
+}
+
+fn main() {
+    let name = String::from("primary");
+    let conn = Conn::builder()
+        .name(&name)
+        .transport(Tcp)
+        .peer("10.0.0.1".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(conn.name, "primary");
+    assert_eq!(conn.peers, vec!["10.0.0.1"]);
+
+    let cache: Cache<u8, &str> = Cache::builder().value("a").value("b").build().unwrap();
+    assert!(cache.key.is_none());
+    assert_eq!(cache.values, vec!["a", "b"]);
+
+    assert_eq!(
+        format!("{:?}", Cache::<u8, &str>::builder()),
+        "CacheBuilder { key: None, values: [] }",
+    );
+
+    let tagged = Tagged::<String>::builder().id(7).build().unwrap();
+    assert_eq!(tagged.id, 7);
+    assert!(tagged.tag.is_none());
+    assert!(format!("{:?}", Tagged::<String>::builder())
+        .starts_with("TaggedBuilder { id: None, __phantom: PhantomData"));
+}
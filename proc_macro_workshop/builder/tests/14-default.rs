@@ -0,0 +1,65 @@
+// Fields marked #[builder(default)] fall back to `Default::default()` when
+// they were never set, and #[builder(default = "...")] evaluates the given
+// expression instead. Neither makes `build` fail.
+//
+// A #[builder(default)] on the struct itself takes every unset field from the
+// struct's own `Default` impl.
+
+use derive_builder::Builder;
+
+#[derive(Debug, Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(default)]
+    args: Vec<String>,
+    #[builder(default = "String::from(\"..\")")]
+    current_dir: String,
+    #[builder(default = "Some(30)")]
+    timeout: Option<u64>,
+}
+
+#[derive(Debug, Builder)]
+#[builder(default)]
+pub struct Server {
+    host: String,
+    port: u16,
+    #[builder(default = "4")]
+    workers: usize,
+    certificate: Option<String>,
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Server {
+            host: "localhost".to_owned(),
+            port: 8080,
+            workers: 1,
+            certificate: Some("cert.pem".to_owned()),
+        }
+    }
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .build()
+        .unwrap();
+    assert!(command.args.is_empty());
+    assert_eq!(command.current_dir, "..");
+    assert_eq!(command.timeout, Some(30));
+
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .current_dir("/tmp".to_owned())
+        .timeout(5)
+        .build()
+        .unwrap();
+    assert_eq!(command.current_dir, "/tmp");
+    assert_eq!(command.timeout, Some(5));
+
+    let server = Server::builder().port(443).build().unwrap();
+    assert_eq!(server.host, "localhost");
+    assert_eq!(server.port, 443);
+    assert_eq!(server.workers, 4);
+    assert_eq!(server.certificate.as_deref(), Some("cert.pem"));
+}
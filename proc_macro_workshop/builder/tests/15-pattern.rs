@@ -0,0 +1,49 @@
+// #[builder(pattern = "...")] picks how setters and `build` take the builder:
+//
+//   - "mutable" (the default): `fn x(&mut self, value) -> &mut Self` and
+//     `build(&self)`, which clones every field.
+//   - "immutable": `fn x(&self, value) -> Self` returning a modified copy,
+//     which makes it easy to derive several variants from one base builder.
+//   - "owned": `fn x(self, value) -> Self` and a consuming `build(self)` that
+//     moves the fields out, so field types do not need to implement Clone.
+
+use derive_builder::Builder;
+
+// Deliberately not Clone.
+#[derive(Debug, PartialEq)]
+pub struct Socket(u16);
+
+#[derive(Debug, Builder)]
+#[builder(pattern = "owned")]
+pub struct Listener {
+    socket: Socket,
+    #[builder(each = "alias")]
+    aliases: Vec<Socket>,
+    backlog: Option<u32>,
+}
+
+#[derive(Debug, Builder)]
+#[builder(pattern = "immutable")]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+}
+
+fn main() {
+    let listener = Listener::builder()
+        .socket(Socket(80))
+        .alias(Socket(8080))
+        .build()
+        .unwrap();
+    assert_eq!(listener.socket, Socket(80));
+    assert_eq!(listener.aliases, vec![Socket(8080)]);
+    assert!(listener.backlog.is_none());
+
+    let base = Command::builder().executable("cargo".to_owned());
+    let build = base.arg("build".to_owned()).build().unwrap();
+    let test = base.arg("test".to_owned()).build().unwrap();
+    assert_eq!(build.args, vec!["build"]);
+    assert_eq!(test.args, vec!["test"]);
+    assert!(base.build().unwrap().args.is_empty());
+}
@@ -0,0 +1,12 @@
+// Only the three known patterns are accepted; anything else is reported on
+// the offending string literal.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(pattern = "borrowed")]
+pub struct Command {
+    executable: String,
+}
+
+fn main() {}
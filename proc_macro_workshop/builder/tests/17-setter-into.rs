@@ -0,0 +1,53 @@
+// #[builder(setter(into))] makes setters generic over `impl Into<T>`, so
+// callers can pass a `&str` where a `String` is stored. It can be given on
+// the struct to apply to every field and turned off again per field with
+// `setter(into = false)`.
+//
+// Setters of `Option<T>` fields take a plain `T` by default. With
+// `setter(strip_option = false)` they take the whole `Option<T>` instead,
+// which is handy when forwarding an optional value.
+
+use derive_builder::Builder;
+use std::path::PathBuf;
+
+#[derive(Debug, Builder)]
+#[builder(setter(into))]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<PathBuf>,
+    #[builder(setter(into = false, strip_option = false))]
+    timeout: Option<u64>,
+}
+
+#[derive(Debug, Builder)]
+pub struct Env {
+    #[builder(setter(into))]
+    key: String,
+    value: String,
+}
+
+fn main() {
+    let configured_timeout: Option<u64> = None;
+
+    let command = Command::builder()
+        .executable("cargo")
+        .arg("build")
+        .arg(String::from("--release"))
+        .current_dir("..")
+        .timeout(configured_timeout)
+        .build()
+        .unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release"]);
+    assert_eq!(command.current_dir, Some(PathBuf::from("..")));
+    assert!(command.timeout.is_none());
+
+    let env = Env::builder()
+        .key("PATH")
+        .value("/usr/bin".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(env.key, "PATH");
+}
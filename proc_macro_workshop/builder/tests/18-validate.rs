@@ -0,0 +1,115 @@
+// #[builder(check = "path")] on a field runs `fn(&T) -> Result<(), E>` on the
+// final value of that field, and #[builder(validate = "path")] on the struct
+// runs `fn(&Builder) -> Result<(), E>` on the whole builder. Both happen
+// inside `build` before the struct is constructed.
+//
+// Any error convertible into `Box<dyn Error + Send + Sync>` can be returned;
+// it ends up in an `Invalid{Field}` or `Invalid` variant of the build error
+// and is exposed through `Error::source`.
+//
+// The functions may share their names with fields, including ones that come
+// earlier in the struct.
+
+use derive_builder::Builder;
+use std::error::Error;
+
+#[derive(Debug, Builder)]
+#[builder(validate = "validate_server")]
+pub struct Server {
+    host: String,
+    #[builder(check = "check_port")]
+    port: u16,
+    #[builder(check = "checks::tls_port", default = "None")]
+    tls_port: Option<u16>,
+}
+
+fn check_port(port: &u16) -> Result<(), String> {
+    if *port == 0 {
+        return Err("port must not be 0".to_owned());
+    }
+    Ok(())
+}
+
+mod checks {
+    pub fn tls_port(port: &Option<u16>) -> Result<(), std::num::TryFromIntError> {
+        if let Some(port) = port {
+            u8::try_from(*port)?;
+        }
+        Ok(())
+    }
+}
+
+fn validate_server(builder: &ServerBuilder) -> Result<(), &'static str> {
+    match (&builder.host, builder.port) {
+        (Some(host), Some(port)) if host == "localhost" && port < 1024 => {
+            Err("privileged port on localhost")
+        }
+        _ => Ok(()),
+    }
+}
+
+#[derive(Debug, Builder)]
+pub struct Limits {
+    #[builder(check = "low")]
+    low: u32,
+    #[builder(default_with = "high")]
+    high: u32,
+    #[builder(default_with = "high")]
+    span: u32,
+}
+
+fn low(low: &u32) -> Result<(), String> {
+    match *low {
+        0 => Err("low must not be 0".to_owned()),
+        _ => Ok(()),
+    }
+}
+
+fn high() -> u32 {
+    100
+}
+
+fn main() {
+    let server = Server::builder()
+        .host("example.org".to_owned())
+        .port(80)
+        .tls_port(120)
+        .build()
+        .unwrap();
+    assert_eq!(server.port, 80);
+
+    let err = Server::builder()
+        .host("example.org".to_owned())
+        .port(0)
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, ServerBuildError::InvalidPort(_)));
+    assert_eq!(err.to_string(), "field `port` is invalid: port must not be 0");
+    assert_eq!(err.source().unwrap().to_string(), "port must not be 0");
+
+    let err = Server::builder()
+        .host("example.org".to_owned())
+        .port(80)
+        .tls_port(443)
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, ServerBuildError::InvalidTlsPort(_)));
+
+    let err = Server::builder()
+        .host("localhost".to_owned())
+        .port(80)
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, ServerBuildError::Invalid(_)));
+    assert_eq!(err.to_string(), "invalid Server: privileged port on localhost");
+
+    let err = Server::builder().port(80).build().unwrap_err();
+    assert!(matches!(err, ServerBuildError::MissingHost));
+    assert!(err.source().is_none());
+
+    let limits = Limits::builder().low(1).build().unwrap();
+    assert_eq!((limits.low, limits.high, limits.span), (1, 100, 100));
+
+    let err = Limits::builder().low(0).build().unwrap_err();
+    assert!(matches!(err, LimitsBuildError::InvalidLow(_)));
+}
@@ -0,0 +1,81 @@
+// Malformed #[builder(...)] attributes are reported as compile errors that
+// point at the offending tokens rather than being silently ignored or making
+// the macro panic:
+//
+//   - a literal of the wrong kind for the option,
+//   - the same option given twice for one field,
+//   - `each` on a field that is not a collection,
+//   - an option the macro does not know about,
+//   - options that contradict each other.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct WrongLiteral {
+    #[builder(each = 1)]
+    args: Vec<String>,
+}
+
+#[derive(Builder)]
+pub struct Duplicate {
+    #[builder(default)]
+    #[builder(default = "1")]
+    retries: u32,
+}
+
+#[derive(Builder)]
+pub struct EachOnScalar {
+    #[builder(each = "arg")]
+    args: String,
+}
+
+#[derive(Builder)]
+pub struct EachOnOption {
+    #[builder(each = "arg")]
+    args: Option<Vec<String>>,
+}
+
+#[derive(Builder)]
+#[builder(setter(into, trim))]
+pub struct UnknownSetterOption {
+    executable: String,
+}
+
+#[derive(Builder)]
+#[builder(pattern)]
+pub struct MissingValue {
+    executable: String,
+}
+
+#[derive(Builder)]
+#[builder(typestate, pattern = "mutable")]
+pub struct Conflicting {
+    executable: String,
+}
+
+#[derive(Builder)]
+pub struct OptionalAndRequired {
+    #[builder(optional, required)]
+    user: Option<String>,
+}
+
+#[derive(Builder)]
+#[builder(const)]
+pub struct ConstWithString {
+    executable: String,
+}
+
+#[derive(Builder)]
+pub struct LazyBorrowed {
+    #[builder(lazy)]
+    executable: String,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "owned")]
+pub struct LazyLifetime<'a> {
+    #[builder(lazy)]
+    executable: &'a str,
+}
+
+fn main() {}
@@ -0,0 +1,59 @@
+// #[builder(each = "...")] is not limited to Vec. Any collection that
+// implements `Default` and `Extend` works, with the element type taken from
+// its type arguments. Maps get a setter taking a key and a value, whether or
+// not they name a hasher.
+//
+//     impl CommandBuilder {
+//         pub fn env(&mut self, key: String, value: String) -> &mut Self {
+//             ...
+//         }
+//     }
+
+use derive_builder::Builder;
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+
+#[derive(Debug, Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: VecDeque<String>,
+    #[builder(each = "env", setter(into))]
+    envs: BTreeMap<String, String>,
+    #[builder(each = "feature")]
+    features: BTreeSet<&'static str>,
+    #[builder(each = "limit")]
+    limits: std::collections::HashMap<&'static str, u64>,
+    #[builder(each = "label")]
+    labels: HashMap<String, String, RandomState>,
+}
+
+#[derive(Debug, Builder)]
+pub struct Index<K: std::hash::Hash + Eq, V> {
+    #[builder(each = "entry")]
+    entries: HashMap<K, V>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .arg("build".to_owned())
+        .env("RUST_LOG", "debug")
+        .env("RUST_BACKTRACE", "1")
+        .feature("serde")
+        .feature("serde")
+        .limit("nofile", 1024)
+        .label("team".to_owned(), "build".to_owned())
+        .build()
+        .unwrap();
+
+    assert_eq!(command.args, ["build"]);
+    assert_eq!(command.envs["RUST_LOG"], "debug");
+    assert_eq!(command.envs.len(), 2);
+    assert_eq!(command.features.len(), 1);
+    assert_eq!(command.limits["nofile"], 1024);
+    assert_eq!(command.labels["team"], "build");
+
+    let index = Index::builder().entry(1, "one").entry(2, "two").build().unwrap();
+    assert_eq!(index.entries[&2], "two");
+}
@@ -0,0 +1,79 @@
+// With #[builder(typestate)] a missing required field is a compile error
+// instead of a runtime error. The builder gets one extra type parameter per
+// required field which is `()` until that field's setter has been called and
+// `(T,)` afterwards:
+//
+//     impl<__Port> ServerBuilder<(), __Port> {
+//         pub fn host(self, value: String) -> ServerBuilder<(String,), __Port> {
+//             ...
+//         }
+//     }
+//
+//     impl ServerBuilder<(String,), (u16,)> {
+//         pub fn build(self) -> Server { ... }
+//     }
+//
+// Setters take the builder by value, and since nothing can be missing any
+// more `build` returns the struct directly.
+
+use derive_builder::Builder;
+
+#[derive(Debug, Builder)]
+#[builder(typestate)]
+pub struct Server {
+    host: String,
+    port: u16,
+    #[builder(each = "alias")]
+    aliases: Vec<String>,
+    #[builder(default = "4")]
+    workers: usize,
+    certificate: Option<String>,
+}
+
+#[derive(Debug, Builder)]
+#[builder(typestate, setter(into))]
+pub struct Route<'a, T> {
+    path: &'a str,
+    #[builder(setter(into = false))]
+    handler: T,
+    #[builder(check = "check_weight")]
+    weight: u8,
+}
+
+fn check_weight(weight: &u8) -> Result<(), String> {
+    if *weight > 100 {
+        return Err(format!("weight {} exceeds 100", weight));
+    }
+    Ok(())
+}
+
+fn main() {
+    // Required setters may be called in any order.
+    let server: Server = Server::builder()
+        .port(8080)
+        .alias("www".to_owned())
+        .host("localhost".to_owned())
+        .certificate("cert.pem".to_owned())
+        .build();
+    assert_eq!(server.host, "localhost");
+    assert_eq!(server.port, 8080);
+    assert_eq!(server.aliases, vec!["www"]);
+    assert_eq!(server.workers, 4);
+
+    // With a `check`, build can still fail at runtime.
+    let route = Route::builder()
+        .handler(|| ())
+        .path("/")
+        .weight(10)
+        .build()
+        .unwrap();
+    assert_eq!(route.path, "/");
+
+    let err = Route::builder()
+        .path("/")
+        .handler(())
+        .weight(200)
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, RouteBuildError::InvalidWeight(_)));
+}
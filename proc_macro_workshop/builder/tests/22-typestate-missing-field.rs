@@ -0,0 +1,15 @@
+// A typestate builder has no `build` method until every required field has
+// been set.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Server {
+    host: String,
+    port: u16,
+}
+
+fn main() {
+    let _server = Server::builder().host("localhost".to_owned()).build();
+}
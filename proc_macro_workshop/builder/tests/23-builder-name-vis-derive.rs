@@ -0,0 +1,50 @@
+// The builder, its error type and the `builder()` constructor take the
+// visibility of the input struct, so a `pub` struct can be built from other
+// modules and crates. The struct-level options
+//
+//     #[builder(name = "...", vis = "...", derive(...))]
+//
+// rename the builder, override its visibility and derive more traits for it
+// next to the `Debug` that is always derived.
+
+use derive_builder::Builder;
+
+mod config {
+    use derive_builder::Builder;
+
+    #[derive(Debug, Builder)]
+    pub struct Server {
+        pub host: String,
+        pub port: Option<u16>,
+    }
+
+    #[derive(Debug, Builder)]
+    #[builder(name = "ClientFactory", vis = "pub(crate)", derive(Clone, PartialEq))]
+    pub struct Client {
+        pub endpoint: String,
+    }
+}
+
+#[derive(Builder)]
+#[builder(derive(Debug, Default))]
+struct Local {
+    name: Option<String>,
+}
+
+fn main() {
+    let server = config::Server::builder()
+        .host("localhost".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(server.host, "localhost");
+    let _: Result<config::Server, config::ServerBuildError> = config::Server::builder().build();
+
+    let mut factory: config::ClientFactory = config::Client::builder();
+    factory.endpoint("https://example.org".to_owned());
+    let copy = factory.clone();
+    assert_eq!(factory, copy);
+    assert_eq!(copy.build().unwrap().endpoint, "https://example.org");
+
+    let local = LocalBuilder::default().build().unwrap();
+    assert!(local.name.is_none());
+}
@@ -0,0 +1,68 @@
+// Fields marked #[builder(skip)] are left out of the builder entirely: there
+// is no setter for them and `build` fills them in with `Default::default()`,
+// or with the struct's own `Default` impl under a struct-level
+// #[builder(default)].
+//
+// #[builder(setter(skip), default = "...")] does the same but fixes the value
+// to the given expression.
+
+use derive_builder::Builder;
+use std::marker::PhantomData;
+
+#[derive(Debug, Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(skip)]
+    history: Vec<String>,
+    #[builder(setter(skip), default = "String::from(\"v1\")")]
+    protocol: String,
+}
+
+#[derive(Debug, Builder)]
+#[builder(default)]
+pub struct Server {
+    host: String,
+    #[builder(skip)]
+    port: u16,
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Server {
+            host: "localhost".to_owned(),
+            port: 8080,
+        }
+    }
+}
+
+#[derive(Debug, Builder)]
+pub struct Handle<T> {
+    id: u32,
+    #[builder(skip)]
+    marker: PhantomData<T>,
+}
+
+// Skipped fields only need their own type to be `Default`, not the type
+// arguments.
+#[derive(Debug)]
+struct NotDefault;
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .build()
+        .unwrap();
+    assert!(command.history.is_empty());
+    assert_eq!(command.protocol, "v1");
+
+    let server = Server::builder()
+        .host("example.com".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(server.host, "example.com");
+    assert_eq!(server.port, 8080);
+
+    let handle: Handle<NotDefault> = Handle::builder().id(7).build().unwrap();
+    assert_eq!(handle.id, 7);
+    let _ = handle.marker;
+}
@@ -0,0 +1,90 @@
+// A field whose type derives Builder itself can be marked
+// #[builder(sub_builder)]. Instead of a setter taking the finished value, the
+// parent builder then hands out the nested builder, creating it on first use:
+//
+//     let mut builder = Config::builder();
+//     builder.server().host("localhost".to_owned()).port(8080);
+//     let config = builder.build()?;
+//
+// `build` builds the children too, and an error of a child is reported as an
+// `Invalid*` variant of the parent's error, naming the field it came from.
+// A sub-builder that was never touched is built fresh, unless the field has a
+// default.
+//
+// If the nested builder was renamed, its type is given as
+// #[builder(sub_builder = "...")]. Either way it has to use the default mutable
+// pattern, whatever the pattern of the parent.
+
+use derive_builder::Builder;
+use std::error::Error;
+
+#[derive(Debug, Builder)]
+pub struct Server {
+    host: String,
+    port: u16,
+}
+
+#[derive(Debug, Builder)]
+#[builder(name = "LimitsConfig")]
+pub struct Limits {
+    #[builder(default = "64")]
+    connections: usize,
+}
+
+#[derive(Debug, Builder)]
+pub struct Config {
+    name: String,
+    #[builder(sub_builder)]
+    server: Server,
+    #[builder(sub_builder = "LimitsConfig")]
+    limits: Limits,
+}
+
+#[derive(Debug, Builder)]
+#[builder(pattern = "owned")]
+pub struct Deployment {
+    #[builder(sub_builder)]
+    config: Config,
+}
+
+fn main() {
+    let mut builder = Config::builder();
+    builder.name("prod".to_owned());
+    builder.server().host("localhost".to_owned()).port(8080);
+    let config = builder.build().unwrap();
+    assert_eq!(config.server.host, "localhost");
+    assert_eq!(config.server.port, 8080);
+    assert_eq!(config.limits.connections, 64);
+
+    builder.limits().connections(8);
+    let config = builder.build().unwrap();
+    assert_eq!(config.limits.connections, 8);
+
+    let mut builder = Config::builder();
+    builder.name("prod".to_owned());
+    builder.server().host("localhost".to_owned());
+    let err = builder.build().unwrap_err();
+    assert!(matches!(err, ConfigBuildError::InvalidServer(_)));
+    assert_eq!(
+        err.to_string(),
+        "field `server` is invalid: field `port` was not set",
+    );
+    assert_eq!(
+        err.source().unwrap().to_string(),
+        "field `port` was not set",
+    );
+
+    // untouched, so built fresh and missing everything
+    let err = Deployment::builder().build().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "field `config` is invalid: field `name` was not set",
+    );
+
+    let mut deployment = Deployment::builder();
+    deployment.config().name("staging".to_owned());
+    deployment.config().server().host("::1".to_owned()).port(22);
+    let deployment = deployment.build().unwrap();
+    assert_eq!(deployment.config.name, "staging");
+    assert_eq!(deployment.config.server.port, 22);
+}
@@ -0,0 +1,74 @@
+// Optional fields are recognized by their type whether it is written as
+// `Option<T>`, `std::option::Option<T>` or `core::option::Option<T>`. A type
+// that merely happens to be called `Option` in some other module is an
+// ordinary required field.
+//
+// Where the type alone is misleading, for example behind a type alias, the
+// field can be marked #[builder(optional)] or #[builder(required)]. The
+// setter of an optional field whose `Option<T>` is hidden behind an alias
+// takes the alias type itself. The override does not turn other types into
+// optional ones: #[builder(optional)] on a field that isn't an `Option` is a
+// type error pointing at that field.
+
+use derive_builder::Builder;
+
+mod custom {
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Option<T>(pub T);
+}
+
+type MaybePort = Option<u16>;
+
+#[derive(Debug, Builder)]
+pub struct Command {
+    executable: String,
+    env: std::option::Option<Vec<String>>,
+    current_dir: core::option::Option<String>,
+    level: custom::Option<u8>,
+    #[builder(optional)]
+    port: MaybePort,
+    #[builder(required)]
+    user: Option<String>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .level(custom::Option(3))
+        .user(None)
+        .build()
+        .unwrap();
+    assert!(command.env.is_none());
+    assert!(command.current_dir.is_none());
+    assert_eq!(command.level, custom::Option(3));
+    assert!(command.port.is_none());
+    assert!(command.user.is_none());
+
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .env(vec![])
+        .current_dir("..".to_owned())
+        .level(custom::Option(1))
+        .port(Some(8080))
+        .user(Some("root".to_owned()))
+        .build()
+        .unwrap();
+    assert_eq!(command.env, Some(vec![]));
+    assert_eq!(command.current_dir.as_deref(), Some(".."));
+    assert_eq!(command.port, Some(8080));
+    assert_eq!(command.user.as_deref(), Some("root"));
+
+    let err = Command::builder()
+        .executable("cargo".to_owned())
+        .user(None)
+        .build()
+        .unwrap_err();
+    assert_eq!(err.to_string(), "field `level` was not set");
+
+    let err = Command::builder()
+        .executable("cargo".to_owned())
+        .level(custom::Option(1))
+        .build()
+        .unwrap_err();
+    assert_eq!(err.to_string(), "field `user` was not set");
+}
@@ -0,0 +1,72 @@
+// A field marked #[builder(try_setter)] gets a second setter prefixed with
+// `try_` that accepts anything convertible through `TryInto` and hands the
+// conversion error back to the caller instead of storing a value:
+//
+//     fn try_port<V: TryInto<u16>>(&mut self, value: V) -> Result<&mut Self, V::Error>
+//
+// Written on the struct, every field that takes a single value gets one.
+
+use derive_builder::Builder;
+use std::convert::TryFrom;
+use std::num::TryFromIntError;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Host(String);
+
+impl TryFrom<&str> for Host {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        if value.is_empty() {
+            return Err("empty host".to_owned());
+        }
+        Ok(Host(value.to_owned()))
+    }
+}
+
+#[derive(Debug, Builder)]
+pub struct Server {
+    #[builder(try_setter)]
+    host: Host,
+    #[builder(try_setter)]
+    port: u16,
+    workers: Option<usize>,
+}
+
+#[derive(Debug, Builder)]
+#[builder(pattern = "owned", try_setter)]
+pub struct Client {
+    timeout: Option<u16>,
+    retries: u8,
+}
+
+#[derive(Debug, Builder)]
+#[builder(typestate, try_setter)]
+pub struct Connection {
+    port: u16,
+}
+
+fn main() -> Result<(), TryFromIntError> {
+    let mut builder = Server::builder();
+    builder.try_host("localhost").unwrap().try_port(8080_u32)?;
+    let server = builder.build().unwrap();
+    assert_eq!(server.host, Host("localhost".to_owned()));
+    assert_eq!(server.port, 8080);
+    assert_eq!(server.workers, None);
+
+    assert_eq!(builder.try_host("").unwrap_err(), "empty host");
+    assert!(builder.try_port(70000_u32).is_err());
+
+    let client = Client::builder()
+        .try_timeout(30_i64)?
+        .try_retries(3_u64)?
+        .build()
+        .unwrap();
+    assert_eq!(client.timeout, Some(30));
+    assert_eq!(client.retries, 3);
+
+    let connection = Connection::builder().try_port(22_i32)?.build();
+    assert_eq!(connection.port, 22);
+
+    Ok(())
+}
@@ -0,0 +1,74 @@
+// An existing instance can be turned back into a builder, either by value
+// through `From<Struct>` or from a reference through `Struct::to_builder`,
+// which needs the struct to be `Clone`. Every field starts out set to the
+// instance's value, so a few of them can be changed before building again.
+
+use derive_builder::Builder;
+
+#[derive(Clone, Debug, Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+    #[builder(skip)]
+    history: Vec<String>,
+}
+
+#[derive(Clone, Debug, Builder)]
+pub struct Server {
+    host: String,
+    port: u16,
+}
+
+#[derive(Clone, Debug, Builder)]
+pub struct Config {
+    #[builder(sub_builder)]
+    server: Server,
+}
+
+#[derive(Clone, Debug, Builder)]
+#[builder(typestate)]
+pub struct Connection {
+    port: u16,
+}
+
+fn main() {
+    let defaults = Command {
+        executable: "cargo".to_owned(),
+        args: vec!["build".to_owned()],
+        current_dir: Some("..".to_owned()),
+        history: vec!["cargo check".to_owned()],
+    };
+
+    let command = defaults
+        .to_builder()
+        .arg("--release".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release"]);
+    assert_eq!(command.current_dir.as_deref(), Some(".."));
+    assert!(command.history.is_empty());
+
+    let mut builder = CommandBuilder::from(defaults);
+    builder.executable("rustc".to_owned());
+    let command = builder.build().unwrap();
+    assert_eq!(command.executable, "rustc");
+    assert_eq!(command.args, vec!["build"]);
+
+    let config = Config {
+        server: Server {
+            host: "localhost".to_owned(),
+            port: 8080,
+        },
+    };
+    let mut builder = config.to_builder();
+    builder.server().port(443);
+    let config = builder.build().unwrap();
+    assert_eq!(config.server.host, "localhost");
+    assert_eq!(config.server.port, 443);
+
+    let connection = Connection { port: 22 }.to_builder().port(2222).build();
+    assert_eq!(connection.port, 2222);
+}
@@ -0,0 +1,60 @@
+// With the `serde` feature of this crate, builders implement
+// `serde::Deserialize` with every field optional, so that each layer of a
+// configuration (defaults, a config file, the environment, ...) can be read
+// into a builder of its own. `merge` then lays one builder over another:
+// fields set in the later layer win, collections are appended to and
+// sub-builders are merged recursively.
+//
+// The caller needs `serde` with its `derive` feature as a dependency.
+// Typestate builders and builders of structs holding references only get
+// `merge`.
+
+use derive_builder::Builder;
+
+#[derive(Debug, Builder)]
+pub struct Server {
+    host: String,
+    port: u16,
+}
+
+#[derive(Debug, Builder)]
+pub struct Config {
+    name: String,
+    #[builder(each = "tag")]
+    tags: Vec<String>,
+    timeout: Option<u64>,
+    #[builder(sub_builder)]
+    server: Server,
+}
+
+fn main() {
+    let mut builder: ConfigBuilder = serde_json::from_str(
+        r#"{
+            "name": "defaults",
+            "tags": ["base"],
+            "timeout": 30,
+            "server": { "host": "localhost", "port": 80 }
+        }"#,
+    )
+    .unwrap();
+
+    let layer: ConfigBuilder = serde_json::from_str(
+        r#"{
+            "tags": ["prod"],
+            "server": { "port": 443 }
+        }"#,
+    )
+    .unwrap();
+    builder.merge(layer);
+
+    let config = builder.build().unwrap();
+    assert_eq!(config.name, "defaults");
+    assert_eq!(config.tags, vec!["base", "prod"]);
+    assert_eq!(config.timeout, Some(30));
+    assert_eq!(config.server.host, "localhost");
+    assert_eq!(config.server.port, 443);
+
+    let empty: ConfigBuilder = serde_json::from_str("{}").unwrap();
+    let err = empty.build().unwrap_err();
+    assert_eq!(err.to_string(), "field `name` was not set");
+}
@@ -0,0 +1,48 @@
+// Every setter repeats the `///` comments of its field, followed by a line
+// saying whether the field has to be set or what it defaults to. The builder
+// struct itself can be documented with #[builder(doc = "...")].
+//
+// Together with docs on everything else the macro generates, this keeps
+// `#![deny(missing_docs)]` crates compiling.
+
+//! Documented builders.
+
+#![deny(missing_docs)]
+
+use derive_builder::Builder;
+
+/// A command to run.
+#[derive(Debug, Builder)]
+#[builder(doc = "Assembles a [`Command`] one field at a time.")]
+pub struct Command {
+    /// Path of the program.
+    executable: String,
+    /// Arguments passed to the program.
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    /// Working directory, the current one if unset.
+    current_dir: Option<String>,
+    /// Seconds until the command is killed.
+    #[builder(default = "30", try_setter)]
+    timeout: u64,
+}
+
+/// A command whose fields are all checked at compile time.
+#[derive(Debug, Builder)]
+#[builder(typestate)]
+pub struct Script {
+    /// The script's source.
+    source: String,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .arg("build".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(command.timeout, 30);
+
+    let script = Script::builder().source("exit".to_owned()).build();
+    assert_eq!(script.source, "exit");
+}
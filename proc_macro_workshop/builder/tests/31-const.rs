@@ -0,0 +1,56 @@
+// With #[builder(const)] on the struct, `builder`, every setter and `build`
+// are `const fn`s, so structs can be assembled in `const` and `static`
+// items. `build` cannot return an error in a `const` context; it panics
+// instead, which makes a missing field a compile error there.
+//
+// `const fn`s may not call trait methods or drop values, so only primitive,
+// reference, array, tuple and `Option` fields are supported, and options
+// that need traits (`each`, `setter(into)`, plain `default`, ...) are
+// rejected. Defaults given as `default = "..."` must be constant expressions.
+
+use derive_builder::Builder;
+
+#[derive(Debug, Builder)]
+#[builder(const)]
+pub struct Pin {
+    name: &'static str,
+    number: u8,
+    #[builder(default = "true")]
+    output: bool,
+    alternate: Option<u8>,
+    levels: [u16; 2],
+}
+
+const LED: Pin = Pin::builder()
+    .name("led")
+    .number(13)
+    .levels([0, 3300])
+    .build();
+
+static TABLE: [Pin; 2] = [
+    LED,
+    Pin::builder()
+        .name("uart_tx")
+        .number(1)
+        .output(false)
+        .alternate(2)
+        .levels([0, 5000])
+        .build(),
+];
+
+fn main() {
+    assert_eq!(TABLE[0].name, "led");
+    assert!(TABLE[0].output);
+    assert_eq!(TABLE[0].alternate, None);
+    assert_eq!(TABLE[1].alternate, Some(2));
+    assert!(!TABLE[1].output);
+    assert_eq!(TABLE[1].levels, [0, 5000]);
+
+    std::panic::set_hook(Box::new(|_| {}));
+    let missing = std::panic::catch_unwind(|| Pin::builder().name("button").build());
+    let payload = missing.unwrap_err();
+    assert_eq!(
+        payload.downcast_ref::<&str>(),
+        Some(&"field `number` was not set"),
+    );
+}
@@ -0,0 +1,68 @@
+// Some defaults are expensive to compute. #[builder(default_with = "path")]
+// names a function taking no arguments that `build` only calls when the field
+// was never set.
+//
+// A field marked #[builder(lazy)] goes one step further: its setter takes a
+// closure returning the field's value, and `build` calls it. The closure is
+// moved into `build`, so lazy fields need #[builder(pattern = "owned")], and
+// it is boxed as `'static`, so their types can't borrow through the struct's
+// lifetimes. In the builder's `Debug` output lazy fields show up as `<lazy>`.
+
+use derive_builder::Builder;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static LOOKUPS: AtomicUsize = AtomicUsize::new(0);
+
+fn hostname() -> String {
+    LOOKUPS.fetch_add(1, Ordering::SeqCst);
+    "localhost".to_owned()
+}
+
+#[derive(Debug, Builder)]
+#[builder(pattern = "owned")]
+pub struct Server {
+    #[builder(default_with = "hostname")]
+    host: String,
+    #[builder(lazy)]
+    certificate: Vec<u8>,
+    #[builder(lazy)]
+    motd: Option<String>,
+}
+
+#[derive(Clone, Builder)]
+#[builder(pattern = "owned")]
+pub struct Cached<T> {
+    #[builder(lazy)]
+    value: T,
+}
+
+fn main() {
+    let server = Server::builder()
+        .host("example.com".to_owned())
+        .certificate(|| b"cert".to_vec())
+        .build()
+        .unwrap();
+    assert_eq!(server.host, "example.com");
+    assert_eq!(server.certificate, b"cert");
+    assert_eq!(server.motd, None);
+    assert_eq!(LOOKUPS.load(Ordering::SeqCst), 0);
+
+    let builder = Server::builder()
+        .certificate(Vec::new)
+        .motd(|| Some("hello".to_owned()));
+    assert_eq!(
+        format!("{:?}", builder),
+        r#"ServerBuilder { host: None, certificate: Some(<lazy>), motd: Some(<lazy>) }"#,
+    );
+    let server = builder.build().unwrap();
+    assert_eq!(server.host, "localhost");
+    assert_eq!(server.motd.as_deref(), Some("hello"));
+    assert_eq!(LOOKUPS.load(Ordering::SeqCst), 1);
+
+    let err = Server::builder().build().unwrap_err();
+    assert_eq!(err.to_string(), "field `certificate` was not set");
+
+    let cached = Cached::builder().value(|| 7).build().unwrap();
+    let cached = cached.to_builder().build().unwrap();
+    assert_eq!(cached.value, 7);
+}
@@ -0,0 +1,14 @@
+// #[builder(optional)] only stands in for `Option` types the macro can't
+// recognize by name, such as aliases. A field of any other type can't be left
+// unset, so marking it optional is reported at the field's type instead of
+// somewhere inside the generated code.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(optional)]
+    count: u32,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+// The same mistake as in 33-optional-non-option.rs, with the `serde` feature
+// enabled. `merge` only takes over optional fields the other builder has set,
+// so it reports the field's type as well.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(optional)]
+    count: u32,
+}
+
+fn main() {}
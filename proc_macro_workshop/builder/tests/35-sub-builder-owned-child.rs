@@ -0,0 +1,20 @@
+// A sub-builder is handed out as `&mut`, so it has to use the default mutable
+// pattern: setters of an owned child could not be called through the
+// reference, nor could its `build` take it by value. Such a child is reported
+// at the parent's field.
+
+use derive_builder::Builder;
+
+#[derive(Debug, Builder)]
+#[builder(pattern = "owned")]
+pub struct Server {
+    port: u16,
+}
+
+#[derive(Debug, Builder)]
+pub struct Config {
+    #[builder(sub_builder)]
+    server: Server,
+}
+
+fn main() {}
@@ -8,6 +8,35 @@ fn tests() {
     t.pass("tests/05-method-chaining.rs");
     t.pass("tests/06-optional-field.rs");
     t.pass("tests/07-repeated-field.rs");
-    // t.compile_fail("tests/08-unrecognized-attribute.rs");
+    t.compile_fail("tests/08-unrecognized-attribute.rs");
     t.pass("tests/09-redefined-prelude-types.rs");
+    t.pass("tests/10-build-error.rs");
+    t.pass("tests/11-tuple-and-unit-structs.rs");
+    t.compile_fail("tests/12-enum.rs");
+    t.pass("tests/13-generics.rs");
+    t.pass("tests/14-default.rs");
+    t.pass("tests/15-pattern.rs");
+    t.compile_fail("tests/16-unknown-pattern.rs");
+    t.pass("tests/17-setter-into.rs");
+    t.pass("tests/18-validate.rs");
+    t.compile_fail("tests/19-malformed-attributes.rs");
+    t.pass("tests/20-each-collections.rs");
+    t.pass("tests/21-typestate.rs");
+    t.compile_fail("tests/22-typestate-missing-field.rs");
+    t.pass("tests/23-builder-name-vis-derive.rs");
+    t.pass("tests/24-skip.rs");
+    t.pass("tests/25-sub-builder.rs");
+    t.pass("tests/26-option-paths.rs");
+    t.pass("tests/27-try-setter.rs");
+    t.pass("tests/28-to-builder.rs");
+    t.pass("tests/30-docs.rs");
+    t.pass("tests/31-const.rs");
+    t.pass("tests/32-lazy-defaults.rs");
+    #[cfg(not(feature = "serde"))]
+    t.compile_fail("tests/33-optional-non-option.rs");
+    t.compile_fail("tests/35-sub-builder-owned-child.rs");
+    #[cfg(feature = "serde")]
+    t.pass("tests/29-serde.rs");
+    #[cfg(feature = "serde")]
+    t.compile_fail("tests/34-optional-non-option-serde.rs");
 }
@@ -0,0 +1,328 @@
+//! Parsing of the `#[debug ...]` attributes on the type and its fields, and
+//! of the `#[display ...]`-like attributes of the sibling derives.
+//!
+//! A format is written as `#[debug = "..."]` or `#[display("...")]`, every
+//! other option as `#[debug(key = ...)]`. Malformed, unknown and repeated
+//! options are reported as `syn::Error`s pointing at the offending tokens.
+
+pub(crate) type Predicates = syn::punctuated::Punctuated<syn::WherePredicate, syn::Token![,]>;
+
+/// Options given through `#[debug(...)]` on the struct or enum itself.
+#[derive(Default)]
+pub(crate) struct TypeAttrs {
+    /// `bound = "..."`: where-predicates replacing all inferred bounds.
+    pub(crate) bound: Option<Predicates>,
+}
+
+/// Options given through `#[debug ...]` on a single field.
+#[derive(Default)]
+pub(crate) struct FieldAttrs {
+    /// `#[debug = "..."]`: format string the field's value is written with.
+    pub(crate) format: Option<syn::LitStr>,
+    /// `#[debug(bound = "...")]`: where-predicates replacing the bounds
+    /// inferred from this field's type.
+    pub(crate) bound: Option<Predicates>,
+    /// `#[debug(skip)]`: leave the field out entirely.
+    pub(crate) skip: bool,
+    /// `#[debug(redact)]` or `#[debug(redact = "len")]`: write a placeholder
+    /// instead of the field's value.
+    pub(crate) redact: Option<Redact>,
+    /// `#[debug(rename = "...")]`: name the field is written under.
+    pub(crate) rename: Option<syn::LitStr>,
+    /// `#[debug(with = "...")]`: function writing the field's value, called
+    /// as `fn(&FieldTy, &mut fmt::Formatter) -> fmt::Result`.
+    pub(crate) with: Option<syn::ExprPath>,
+}
+
+/// Options given through `#[display(...)]`, or the attribute of another
+/// template-based derive, on the type or one of its variants.
+#[derive(Default)]
+pub(crate) struct TemplateAttrs {
+    /// `#[display("...")]`: format string, interpolating the fields by name
+    /// or by position.
+    pub(crate) template: Option<syn::LitStr>,
+    /// `#[display(bound = "...")]`: where-predicates replacing all inferred
+    /// bounds. Only allowed on the type.
+    pub(crate) bound: Option<Predicates>,
+}
+
+/// What a redacted field is written as.
+pub(crate) enum Redact {
+    /// `<redacted>`
+    Hidden,
+    /// `<redacted len=N>`, with `N` taken from the value's `len()`
+    Len,
+}
+
+/// Pairs of field options that contradict each other.
+const CONFLICTS: &[(&str, &str)] = &[
+    ("skip", "redact"),
+    ("skip", "rename"),
+    ("skip", "debug = \"...\""),
+    ("skip", "with"),
+    ("redact", "debug = \"...\""),
+    ("redact", "with"),
+    ("with", "debug = \"...\""),
+];
+
+pub(crate) fn parse_type_attrs(attrs: &[syn::Attribute]) -> syn::Result<TypeAttrs> {
+    let mut type_attrs = TypeAttrs::default();
+
+    for meta in metas(attrs, "debug")? {
+        match meta {
+            AttrMeta::Format(lit) => {
+                return Err(syn::Error::new_spanned(
+                    lit,
+                    "`debug = \"...\"` can only be used on fields",
+                ))
+            }
+            AttrMeta::Option(meta) => match key(meta.path()).as_str() {
+                "bound" => set(&mut type_attrs.bound, &meta, bound(&meta)?)?,
+                _ => return Err(unknown(&meta, "debug")),
+            },
+        }
+    }
+
+    Ok(type_attrs)
+}
+
+/// Variants take no options of their own; the field options are rejected
+/// explicitly, so that e.g. a `redact` meant for the whole variant can't go
+/// unnoticed.
+pub(crate) fn parse_variant_attrs(attrs: &[syn::Attribute]) -> syn::Result<()> {
+    match metas(attrs, "debug")?.into_iter().next() {
+        None => Ok(()),
+        Some(AttrMeta::Format(lit)) => Err(syn::Error::new_spanned(
+            lit,
+            "`debug = \"...\"` can only be used on fields",
+        )),
+        Some(AttrMeta::Option(meta)) => match key(meta.path()).as_str() {
+            key @ ("bound" | "skip" | "redact" | "rename" | "with") => {
+                Err(syn::Error::new_spanned(
+                    meta.path(),
+                    format!("`{}` can only be used on fields", key),
+                ))
+            }
+            _ => Err(unknown(&meta, "debug")),
+        },
+    }
+}
+
+pub(crate) fn parse_field_attrs(attrs: &[syn::Attribute]) -> syn::Result<FieldAttrs> {
+    let mut field_attrs = FieldAttrs::default();
+    // the options given so far, for reporting conflicts
+    let mut given: Vec<(&str, proc_macro2::Span)> = Vec::new();
+
+    for meta in metas(attrs, "debug")? {
+        let option = match meta {
+            AttrMeta::Format(lit) => {
+                if field_attrs.format.is_some() {
+                    return Err(syn::Error::new_spanned(lit, "duplicate `debug` format"));
+                }
+                let span = lit.span();
+                field_attrs.format = Some(lit);
+                ("debug = \"...\"", span)
+            }
+            AttrMeta::Option(meta) => {
+                let span = syn::spanned::Spanned::span(meta.path());
+                match key(meta.path()).as_str() {
+                    "bound" => {
+                        set(&mut field_attrs.bound, &meta, bound(&meta)?)?;
+                        ("bound", span)
+                    }
+                    "skip" => {
+                        flag(&meta)?;
+                        if field_attrs.skip {
+                            return Err(duplicate(&meta));
+                        }
+                        field_attrs.skip = true;
+                        ("skip", span)
+                    }
+                    "redact" => {
+                        set(&mut field_attrs.redact, &meta, redact(&meta)?)?;
+                        ("redact", span)
+                    }
+                    "rename" => {
+                        set(&mut field_attrs.rename, &meta, string(&meta)?)?;
+                        ("rename", span)
+                    }
+                    "with" => {
+                        set(&mut field_attrs.with, &meta, string(&meta)?.parse()?)?;
+                        ("with", span)
+                    }
+                    _ => return Err(unknown(&meta, "debug")),
+                }
+            }
+        };
+
+        for (earlier, _) in &given {
+            if CONFLICTS.contains(&(earlier, option.0)) || CONFLICTS.contains(&(option.0, earlier))
+            {
+                return Err(syn::Error::new(
+                    option.1,
+                    format!("`{}` cannot be used with `{}`", option.0, earlier),
+                ));
+            }
+        }
+        given.push(option);
+    }
+
+    Ok(field_attrs)
+}
+
+/// `on_variant` rejects the options that only make sense on the type.
+pub(crate) fn parse_template_attrs(
+    attrs: &[syn::Attribute],
+    name: &str,
+    on_variant: bool,
+) -> syn::Result<TemplateAttrs> {
+    let mut template_attrs = TemplateAttrs::default();
+
+    for meta in metas(attrs, name)? {
+        match meta {
+            AttrMeta::Format(lit) => {
+                if template_attrs.template.is_some() {
+                    return Err(syn::Error::new_spanned(
+                        lit,
+                        format!("duplicate `{}` format", name),
+                    ));
+                }
+                template_attrs.template = Some(lit);
+            }
+            AttrMeta::Option(meta) => match key(meta.path()).as_str() {
+                "bound" if on_variant => {
+                    return Err(syn::Error::new_spanned(
+                        meta.path(),
+                        "`bound` can only be used on the type",
+                    ))
+                }
+                "bound" => set(&mut template_attrs.bound, &meta, bound(&meta)?)?,
+                _ => return Err(unknown(&meta, name)),
+            },
+        }
+    }
+
+    Ok(template_attrs)
+}
+
+/// A single `#[name = "..."]` or one entry out of `#[name(...)]`, where a
+/// bare string literal entry is a format as well.
+enum AttrMeta {
+    Format(syn::LitStr),
+    Option(syn::Meta),
+}
+
+fn metas(attrs: &[syn::Attribute], name: &str) -> syn::Result<Vec<AttrMeta>> {
+    let mut metas = Vec::new();
+
+    for attr in attrs.iter().filter(|a| a.path.is_ident(name)) {
+        match attr.parse_meta()? {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                lit: syn::Lit::Str(lit),
+                ..
+            }) => metas.push(AttrMeta::Format(lit)),
+            syn::Meta::NameValue(nv) => {
+                return Err(syn::Error::new_spanned(nv.lit, "expected string literal"))
+            }
+            syn::Meta::List(list) => {
+                for nested in list.nested {
+                    match nested {
+                        syn::NestedMeta::Meta(meta) => metas.push(AttrMeta::Option(meta)),
+                        syn::NestedMeta::Lit(syn::Lit::Str(lit)) => {
+                            metas.push(AttrMeta::Format(lit))
+                        }
+                        syn::NestedMeta::Lit(lit) => {
+                            return Err(syn::Error::new_spanned(
+                                lit,
+                                format!("expected a {} option", name),
+                            ))
+                        }
+                    }
+                }
+            }
+            meta @ syn::Meta::Path(_) => {
+                return Err(syn::Error::new_spanned(
+                    meta,
+                    format!(r#"expected `{0} = "..."` or `{0}(...)`"#, name),
+                ))
+            }
+        }
+    }
+
+    Ok(metas)
+}
+
+fn key(path: &syn::Path) -> String {
+    quote::ToTokens::to_token_stream(path)
+        .to_string()
+        .replace(' ', "")
+}
+
+fn unknown(meta: &syn::Meta, name: &str) -> syn::Error {
+    syn::Error::new_spanned(meta.path(), format!("unknown {} option", name))
+}
+
+/// Stores `value` in `slot`, unless an earlier `meta` already did.
+fn set<T>(slot: &mut Option<T>, meta: &syn::Meta, value: T) -> syn::Result<()> {
+    if slot.is_some() {
+        return Err(duplicate(meta));
+    }
+    *slot = Some(value);
+
+    Ok(())
+}
+
+fn duplicate(meta: &syn::Meta) -> syn::Error {
+    syn::Error::new_spanned(
+        meta.path(),
+        format!("duplicate `{}` option", key(meta.path())),
+    )
+}
+
+/// `bound = "..."`, where the string holds where-predicates.
+fn bound(meta: &syn::Meta) -> syn::Result<Predicates> {
+    string(meta)?.parse_with(Predicates::parse_terminated)
+}
+
+/// `redact` or `redact = "len"`.
+fn redact(meta: &syn::Meta) -> syn::Result<Redact> {
+    match meta {
+        syn::Meta::Path(_) => Ok(Redact::Hidden),
+        syn::Meta::NameValue(syn::MetaNameValue {
+            lit: syn::Lit::Str(lit),
+            ..
+        }) if lit.value() == "len" => Ok(Redact::Len),
+        _ => Err(syn::Error::new_spanned(
+            meta,
+            r#"expected `redact` or `redact = "len"`"#,
+        )),
+    }
+}
+
+/// `key = "..."`
+fn string(meta: &syn::Meta) -> syn::Result<syn::LitStr> {
+    match meta {
+        syn::Meta::NameValue(syn::MetaNameValue {
+            lit: syn::Lit::Str(lit),
+            ..
+        }) => Ok(lit.clone()),
+        syn::Meta::NameValue(nv) => {
+            Err(syn::Error::new_spanned(&nv.lit, "expected string literal"))
+        }
+        _ => Err(syn::Error::new_spanned(
+            meta,
+            format!("expected `{} = \"...\"`", key(meta.path())),
+        )),
+    }
+}
+
+/// A bare `key`, without a value.
+fn flag(meta: &syn::Meta) -> syn::Result<()> {
+    match meta {
+        syn::Meta::Path(_) => Ok(()),
+        _ => Err(syn::Error::new_spanned(
+            meta,
+            format!("expected `{}` without a value", key(meta.path())),
+        )),
+    }
+}
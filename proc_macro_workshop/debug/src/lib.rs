@@ -1,8 +1,333 @@
-use proc_macro::TokenStream;
+mod attr;
+mod template;
 
-#[proc_macro_derive(CustomDebug)]
-pub fn derive(input: TokenStream) -> TokenStream {
-    let _ = input;
+#[proc_macro_derive(CustomDebug, attributes(debug))]
+pub fn derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
 
-    unimplemented!()
+    expand(&input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+#[proc_macro_derive(CustomDisplay, attributes(display))]
+pub fn derive_display(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let fmt_trait = template::FmtTrait {
+        derive: "CustomDisplay",
+        attr: "display",
+        path: quote::quote! { std::fmt::Display },
+    };
+    derive_template(input, &fmt_trait)
+}
+
+#[proc_macro_derive(CustomLowerHex, attributes(lower_hex))]
+pub fn derive_lower_hex(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let fmt_trait = template::FmtTrait {
+        derive: "CustomLowerHex",
+        attr: "lower_hex",
+        path: quote::quote! { std::fmt::LowerHex },
+    };
+    derive_template(input, &fmt_trait)
+}
+
+#[proc_macro_derive(CustomBinary, attributes(binary))]
+pub fn derive_binary(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let fmt_trait = template::FmtTrait {
+        derive: "CustomBinary",
+        attr: "binary",
+        path: quote::quote! { std::fmt::Binary },
+    };
+    derive_template(input, &fmt_trait)
+}
+
+fn derive_template(
+    input: proc_macro::TokenStream,
+    fmt_trait: &template::FmtTrait,
+) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+
+    template::expand(&input, fmt_trait)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+fn expand(input: &syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    // extract relevant informations
+    // -----------------------------
+    let type_attrs = attr::parse_type_attrs(&input.attrs)?;
+    let input_ident = &input.ident;
+
+    // construct token-streams
+    // -----------------------
+    let mut inferred = Vec::new();
+
+    let ts_body = match &input.data {
+        syn::Data::Struct(data) => {
+            let arm = debug_arm(
+                quote::quote! { Self },
+                input_ident,
+                &data.fields,
+                &input.generics,
+                &mut inferred,
+            )?;
+            quote::quote! {
+                match self {
+                    #arm
+                }
+            }
+        }
+        syn::Data::Enum(data) if data.variants.is_empty() => quote::quote! { match *self {} },
+        syn::Data::Enum(data) => {
+            let arms = data
+                .variants
+                .iter()
+                .map(|v| {
+                    let ident = &v.ident;
+                    attr::parse_variant_attrs(&v.attrs)?;
+                    debug_arm(
+                        quote::quote! { Self::#ident },
+                        ident,
+                        &v.fields,
+                        &input.generics,
+                        &mut inferred,
+                    )
+                })
+                .collect::<syn::Result<Vec<_>>>()?;
+            quote::quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        syn::Data::Union(data) => {
+            return Err(syn::Error::new_spanned(
+                data.union_token,
+                "CustomDebug can only be derived for structs and enums, not unions",
+            ))
+        }
+    };
+
+    let generics = with_bounds(&input.generics, type_attrs.bound, inferred);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    // combine all token-streams
+    // -------------------------
+    Ok(quote::quote! {
+        impl #impl_generics std::fmt::Debug for #input_ident #ty_generics #where_clause {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                #ts_body
+            }
+        }
+    })
+}
+
+/// `generics` with the bounds of an impl added: `bound`, given on the type,
+/// replaces whatever the fields need, `inferred` otherwise. Fields of the same
+/// type only add their bound once.
+fn with_bounds(
+    generics: &syn::Generics,
+    bound: Option<attr::Predicates>,
+    inferred: Vec<syn::WherePredicate>,
+) -> syn::Generics {
+    let predicates = match bound {
+        Some(bound) => bound.into_iter().collect(),
+        None => inferred,
+    };
+    let mut generics = generics.clone();
+    let where_clause = generics.make_where_clause();
+    let mut seen = Vec::new();
+    for predicate in predicates {
+        let key = quote::ToTokens::to_token_stream(&predicate).to_string();
+        if !seen.contains(&key) {
+            seen.push(key);
+            where_clause.predicates.push(predicate);
+        }
+    }
+    generics
+}
+
+/// A match arm destructuring `path` with the given `fields` and writing them
+/// the way `#[derive(Debug)]` would, under the name `ident`. The bounds the
+/// fields need are added to `bounds`.
+fn debug_arm(
+    path: proc_macro2::TokenStream,
+    ident: &syn::Ident,
+    fields: &syn::Fields,
+    generics: &syn::Generics,
+    bounds: &mut Vec<syn::WherePredicate>,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let name = syn::ext::IdentExt::unraw(ident).to_string();
+
+    // fields are bound by position, so none of them can shadow `f`
+    let mut patterns = Vec::new();
+    let mut labels = Vec::new();
+    let mut values = Vec::new();
+    let mut uses_with = false;
+    for (index, field) in fields.iter().enumerate() {
+        let attrs = attr::parse_field_attrs(&field.attrs)?;
+        let binding = quote::format_ident!("__self_{}", index);
+
+        // only values written through `Debug` itself need bounds
+        match attrs.bound {
+            Some(bound) => bounds.extend(bound),
+            None if attrs.skip || attrs.redact.is_some() || attrs.with.is_some() => {}
+            None => {
+                let mut tys = Vec::new();
+                bounded_tys(&field.ty, generics, &mut tys);
+                // a format string asks for whatever its specs name instead,
+                // e.g. `Binary` for `{:08b}`
+                let traits = match &attrs.format {
+                    Some(format) => template::placeholders(format)?
+                        .into_iter()
+                        .flat_map(|placeholder| placeholder.trait_path)
+                        .collect(),
+                    None => vec![quote::quote! { std::fmt::Debug }],
+                };
+                for trait_path in &traits {
+                    bounds.extend(tys.iter().map(|ty| -> syn::WherePredicate {
+                        syn::parse_quote! { #ty: #trait_path }
+                    }));
+                }
+            }
+        }
+
+        // placeholders are written through `format_args!`, which ignores `{:#?}`
+        let value = match (&attrs.redact, &attrs.format) {
+            _ if attrs.skip => None,
+            (Some(attr::Redact::Hidden), _) => {
+                Some(quote::quote! { &std::format_args!("<redacted>") })
+            }
+            (Some(attr::Redact::Len), _) => {
+                Some(quote::quote! { &std::format_args!("<redacted len={}>", #binding.len()) })
+            }
+            (None, _) if attrs.with.is_some() => {
+                let with = &attrs.with;
+                uses_with = true;
+                Some(quote::quote! { &__DebugWith(#binding, #with) })
+            }
+            (None, Some(format)) => Some(quote::quote! { &std::format_args!(#format, #binding) }),
+            (None, None) => Some(quote::quote! { #binding }),
+        };
+        let uses_binding = !attrs.skip && !matches!(attrs.redact, Some(attr::Redact::Hidden));
+        let pattern = match uses_binding {
+            true => quote::quote! { #binding },
+            false => quote::quote! { _ },
+        };
+        patterns.push(match &field.ident {
+            Some(member) => quote::quote! { #member: #pattern },
+            None => pattern,
+        });
+
+        let label = match (&attrs.rename, &field.ident) {
+            (Some(rename), Some(_)) => rename.value(),
+            (Some(rename), None) => {
+                return Err(syn::Error::new_spanned(
+                    rename,
+                    "`rename` can only be used on named fields",
+                ))
+            }
+            (None, Some(ident)) => syn::ext::IdentExt::unraw(ident).to_string(),
+            (None, None) => String::new(),
+        };
+        if let Some(value) = value {
+            labels.push(label);
+            values.push(value);
+        }
+    }
+
+    // adapts `#[debug(with = "...")]` functions to `Debug`, only where used
+    let ts_with = match uses_with {
+        true => quote::quote! {
+            struct __DebugWith<'__a, __T: ?Sized, __F>(&'__a __T, __F)
+            where
+                __F: Fn(&__T, &mut std::fmt::Formatter<'_>) -> std::fmt::Result;
+
+            impl<'__a, __T: ?Sized, __F> std::fmt::Debug for __DebugWith<'__a, __T, __F>
+            where
+                __F: Fn(&__T, &mut std::fmt::Formatter<'_>) -> std::fmt::Result,
+            {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    (self.1)(self.0, f)
+                }
+            }
+        },
+        false => proc_macro2::TokenStream::new(),
+    };
+
+    Ok(match fields {
+        syn::Fields::Named(_) => quote::quote! {
+            #path { #(#patterns),* } => {
+                #ts_with
+                f.debug_struct(#name)
+                    #(.field(#labels, #values))*
+                    .finish()
+            }
+        },
+        syn::Fields::Unnamed(_) => quote::quote! {
+            #path(#(#patterns),*) => {
+                #ts_with
+                f.debug_tuple(#name)
+                    #(.field(#values))*
+                    .finish()
+            }
+        },
+        syn::Fields::Unit => quote::quote! {
+            #path => f.write_str(#name),
+        },
+    })
+}
+
+/// The types within `ty` that decide whether it is `Debug`: the type
+/// parameters it uses and their associated types, e.g. `T` and `T::Value` for
+/// `Vec<(T, T::Value)>`. `PhantomData<T>` is `Debug` whatever `T` is, so
+/// nothing inside of it counts.
+fn bounded_tys(ty: &syn::Type, generics: &syn::Generics, tys: &mut Vec<syn::Type>) {
+    let is_param = |ty: &syn::Type| match ty {
+        syn::Type::Path(syn::TypePath { qself: None, path }) => {
+            path.leading_colon.is_none()
+                && generics
+                    .type_params()
+                    .any(|param| param.ident == path.segments[0].ident)
+        }
+        _ => false,
+    };
+
+    match ty {
+        // `T`, `T::Value` and `<T as Trait>::Value`
+        syn::Type::Path(_) if is_param(ty) => tys.push(ty.clone()),
+        syn::Type::Path(syn::TypePath {
+            qself: Some(qself), ..
+        }) if is_param(&qself.ty) => tys.push(ty.clone()),
+        syn::Type::Path(syn::TypePath { qself: Some(_), .. }) => {}
+        syn::Type::Path(syn::TypePath { path, .. }) => {
+            if path.segments.last().unwrap().ident == "PhantomData" {
+                return;
+            }
+            for segment in &path.segments {
+                if let syn::PathArguments::AngleBracketed(generic) = &segment.arguments {
+                    for arg in &generic.args {
+                        match arg {
+                            syn::GenericArgument::Type(ty) => bounded_tys(ty, generics, tys),
+                            syn::GenericArgument::Binding(binding) => {
+                                bounded_tys(&binding.ty, generics, tys)
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        }
+        syn::Type::Reference(reference) => bounded_tys(&reference.elem, generics, tys),
+        syn::Type::Array(array) => bounded_tys(&array.elem, generics, tys),
+        syn::Type::Slice(slice) => bounded_tys(&slice.elem, generics, tys),
+        syn::Type::Paren(paren) => bounded_tys(&paren.elem, generics, tys),
+        syn::Type::Group(group) => bounded_tys(&group.elem, generics, tys),
+        syn::Type::Tuple(tuple) => {
+            for elem in &tuple.elems {
+                bounded_tys(elem, generics, tys);
+            }
+        }
+        // pointers and function pointers are `Debug` for any type
+        _ => {}
+    }
 }
@@ -0,0 +1,361 @@
+//! The template-based derives `CustomDisplay`, `CustomLowerHex` and
+//! `CustomBinary`.
+//!
+//! Each of them writes the type through a format string given as
+//! `#[display("...")]` (or `#[lower_hex("...")]`, `#[binary("...")]`) on a
+//! struct or on every variant of an enum, interpolating the fields by name or
+//! by position. Types with a single field may leave it out to forward to that
+//! field's implementation of the trait instead.
+
+/// One of the formatting traits derived from a template.
+pub(crate) struct FmtTrait {
+    /// Name of the derive, for error messages.
+    pub(crate) derive: &'static str,
+    /// Name of the attribute holding the templates.
+    pub(crate) attr: &'static str,
+    /// Path of the implemented trait.
+    pub(crate) path: proc_macro2::TokenStream,
+}
+
+pub(crate) fn expand(
+    input: &syn::DeriveInput,
+    fmt_trait: &FmtTrait,
+) -> syn::Result<proc_macro2::TokenStream> {
+    // extract relevant informations
+    // -----------------------------
+    let type_attrs = crate::attr::parse_template_attrs(&input.attrs, fmt_trait.attr, false)?;
+    let input_ident = &input.ident;
+    let trait_path = &fmt_trait.path;
+
+    // construct token-streams
+    // -----------------------
+    let mut inferred = Vec::new();
+
+    let ts_body = match &input.data {
+        syn::Data::Struct(data) => {
+            let arm = template_arm(
+                quote::quote! { Self },
+                input_ident,
+                type_attrs.template.as_ref(),
+                &data.fields,
+                &input.generics,
+                fmt_trait,
+                &mut inferred,
+            )?;
+            quote::quote! {
+                match self {
+                    #arm
+                }
+            }
+        }
+        syn::Data::Enum(data) if data.variants.is_empty() => quote::quote! { match *self {} },
+        syn::Data::Enum(data) => {
+            if let Some(template) = &type_attrs.template {
+                return Err(syn::Error::new_spanned(
+                    template,
+                    format!(
+                        "the `{}` format of an enum goes on each of its variants",
+                        fmt_trait.attr
+                    ),
+                ));
+            }
+            let arms = data
+                .variants
+                .iter()
+                .map(|v| {
+                    let ident = &v.ident;
+                    let attrs = crate::attr::parse_template_attrs(&v.attrs, fmt_trait.attr, true)?;
+                    template_arm(
+                        quote::quote! { Self::#ident },
+                        ident,
+                        attrs.template.as_ref(),
+                        &v.fields,
+                        &input.generics,
+                        fmt_trait,
+                        &mut inferred,
+                    )
+                })
+                .collect::<syn::Result<Vec<_>>>()?;
+            quote::quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        syn::Data::Union(data) => {
+            return Err(syn::Error::new_spanned(
+                data.union_token,
+                format!(
+                    "{} can only be derived for structs and enums, not unions",
+                    fmt_trait.derive
+                ),
+            ))
+        }
+    };
+
+    let generics = crate::with_bounds(&input.generics, type_attrs.bound, inferred);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    // combine all token-streams
+    // -------------------------
+    Ok(quote::quote! {
+        impl #impl_generics #trait_path for #input_ident #ty_generics #where_clause {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                #ts_body
+            }
+        }
+    })
+}
+
+/// A match arm destructuring `path` with the given `fields` and writing them
+/// through `template`, or forwarding to the only field without one. The
+/// bounds the fields need are added to `bounds`.
+fn template_arm(
+    path: proc_macro2::TokenStream,
+    ident: &syn::Ident,
+    template: Option<&syn::LitStr>,
+    fields: &syn::Fields,
+    generics: &syn::Generics,
+    fmt_trait: &FmtTrait,
+    bounds: &mut Vec<syn::WherePredicate>,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let trait_path = &fmt_trait.path;
+
+    for field in fields {
+        if let Some(attr) = field.attrs.iter().find(|a| a.path.is_ident(fmt_trait.attr)) {
+            return Err(syn::Error::new_spanned(
+                attr,
+                format!(
+                    "`{}` can only be used on the type and its variants",
+                    fmt_trait.attr
+                ),
+            ));
+        }
+    }
+
+    let placeholders = match template {
+        Some(template) => placeholders(template)?,
+        None if fields.len() == 1 => {
+            let field = fields.iter().next().unwrap();
+            vec![Placeholder {
+                arg: match &field.ident {
+                    Some(member) => Arg::Named(syn::ext::IdentExt::unraw(member).to_string()),
+                    None => Arg::Index(0),
+                },
+                trait_path: Some(trait_path.clone()),
+            }]
+        }
+        None => {
+            return Err(syn::Error::new_spanned(
+                ident,
+                format!(
+                    "{} needs a `#[{}(\"...\")]` format here",
+                    fmt_trait.derive, fmt_trait.attr
+                ),
+            ))
+        }
+    };
+    // positional arguments can't be left out in between
+    let positional = placeholders
+        .iter()
+        .filter_map(|placeholder| match placeholder.arg {
+            Arg::Index(index) => Some(index + 1),
+            Arg::Named(_) => None,
+        })
+        .max()
+        .unwrap_or(0);
+
+    let mut patterns = Vec::new();
+    let mut args = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        let binding = quote::format_ident!("__self_{}", index);
+        let arg = match &field.ident {
+            Some(member) => Arg::Named(syn::ext::IdentExt::unraw(member).to_string()),
+            None => Arg::Index(index),
+        };
+        let used = match &arg {
+            Arg::Named(_) => placeholders
+                .iter()
+                .any(|placeholder| placeholder.arg == arg),
+            Arg::Index(index) => *index < positional,
+        };
+        // widths and precisions have to be `usize` itself
+        let is_count = placeholders
+            .iter()
+            .any(|placeholder| placeholder.arg == arg && placeholder.trait_path.is_none());
+        let value = match is_count {
+            true => quote::quote! { *#binding },
+            false => quote::quote! { #binding },
+        };
+        let name = match &field.ident {
+            // `{type}` can't refer to `r#type`, format arguments are never
+            // keywords
+            Some(member) if used && template.is_some() && member.to_string().starts_with("r#") => {
+                return Err(syn::Error::new_spanned(
+                    template,
+                    format!(
+                        "raw identifier fields like `{}` cannot be used in formats",
+                        member
+                    ),
+                ));
+            }
+            Some(member) => {
+                let name = syn::ext::IdentExt::unraw(member);
+                used.then(|| quote::quote! { #name = #value })
+            }
+            // `format_args!` complains about positional arguments it never
+            // uses, and the fields can't be passed out of order
+            None if used
+                && !placeholders
+                    .iter()
+                    .any(|placeholder| placeholder.arg == arg) =>
+            {
+                return Err(syn::Error::new_spanned(
+                    template,
+                    format!(
+                        "field {} has to be used in the format, later fields are referred to by position",
+                        index
+                    ),
+                ));
+            }
+            None => used.then_some(value),
+        };
+
+        patterns.push(match (&name, &field.ident) {
+            (Some(_), Some(member)) => quote::quote! { #member: #binding },
+            (None, Some(member)) => quote::quote! { #member: _ },
+            (Some(_), None) => quote::quote! { #binding },
+            (None, None) => quote::quote! { _ },
+        });
+        args.extend(name);
+
+        // every placeholder needs the trait its format spec asks for
+        let mut tys = Vec::new();
+        crate::bounded_tys(&field.ty, generics, &mut tys);
+        let traits = placeholders
+            .iter()
+            .filter(|placeholder| placeholder.arg == arg)
+            .flat_map(|placeholder| &placeholder.trait_path);
+        for trait_path in traits {
+            bounds.extend(tys.iter().map(|ty| -> syn::WherePredicate {
+                syn::parse_quote! { #ty: #trait_path }
+            }));
+        }
+    }
+
+    let ts_pattern = match fields {
+        syn::Fields::Named(_) => quote::quote! { #path { #(#patterns),* } },
+        syn::Fields::Unnamed(_) => quote::quote! { #path(#(#patterns),*) },
+        syn::Fields::Unit => quote::quote! { #path },
+    };
+
+    Ok(match template {
+        Some(template) => quote::quote! {
+            #ts_pattern => std::write!(f, #template #(, #args)*),
+        },
+        None => quote::quote! {
+            #ts_pattern => #trait_path::fmt(__self_0, f),
+        },
+    })
+}
+
+/// An argument referred to by a format string: the value of a `{...}`, or a
+/// width or precision given as `name$`, `1$` or `.*`.
+pub(crate) struct Placeholder {
+    arg: Arg,
+    /// The formatting trait the argument is written with, `None` for widths
+    /// and precisions.
+    pub(crate) trait_path: Option<proc_macro2::TokenStream>,
+}
+
+#[derive(PartialEq)]
+enum Arg {
+    Named(String),
+    Index(usize),
+}
+
+impl Arg {
+    fn parse(arg: &str, template: &syn::LitStr) -> syn::Result<Arg> {
+        if arg.starts_with("r#") {
+            return Err(syn::Error::new_spanned(
+                template,
+                format!("raw identifiers like `{}` cannot be used in formats", arg),
+            ));
+        }
+        Ok(match arg.parse() {
+            Ok(index) => Arg::Index(index),
+            Err(_) => Arg::Named(arg.to_owned()),
+        })
+    }
+}
+
+/// The arguments `template` refers to, with implicit positions made
+/// explicit.
+pub(crate) fn placeholders(template: &syn::LitStr) -> syn::Result<Vec<Placeholder>> {
+    let value = template.value();
+    let mut placeholders = Vec::new();
+    let mut next = 0;
+    let mut implicit = || {
+        next += 1;
+        Arg::Index(next - 1)
+    };
+
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '{' {
+            continue;
+        }
+        if chars.peek() == Some(&'{') {
+            chars.next();
+            continue;
+        }
+
+        let inner: String = chars.by_ref().take_while(|&c| c != '}').collect();
+        let (arg, spec) = inner.split_once(':').unwrap_or((&inner, ""));
+
+        // `.*` takes the precision from the next positional argument, ahead
+        // of the value itself
+        if spec.contains(".*") {
+            let arg = implicit();
+            placeholders.push(Placeholder {
+                arg,
+                trait_path: None,
+            });
+        }
+        let arg = match arg.trim() {
+            "" => implicit(),
+            arg => Arg::parse(arg, template)?,
+        };
+
+        // widths and precisions named as `name$` or `1$`
+        for (index, _) in spec.match_indices('$') {
+            let start = spec[..index]
+                .rfind(|c: char| !c.is_alphanumeric() && c != '_')
+                .map_or(0, |start| start + 1);
+            if start < index {
+                placeholders.push(Placeholder {
+                    arg: Arg::parse(&spec[start..index], template)?,
+                    trait_path: None,
+                });
+            }
+        }
+
+        // the type comes last, after fill, alignment, width and precision
+        let trait_path = match spec.chars().last() {
+            Some('?') => quote::quote! { std::fmt::Debug },
+            Some('x') => quote::quote! { std::fmt::LowerHex },
+            Some('X') => quote::quote! { std::fmt::UpperHex },
+            Some('o') => quote::quote! { std::fmt::Octal },
+            Some('b') => quote::quote! { std::fmt::Binary },
+            Some('e') => quote::quote! { std::fmt::LowerExp },
+            Some('E') => quote::quote! { std::fmt::UpperExp },
+            _ => quote::quote! { std::fmt::Display },
+        };
+        placeholders.push(Placeholder {
+            arg,
+            trait_path: Some(trait_path),
+        });
+    }
+
+    Ok(placeholders)
+}
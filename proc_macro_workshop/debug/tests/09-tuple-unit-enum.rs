@@ -0,0 +1,55 @@
+// Besides structs with named fields, CustomDebug handles tuple structs, unit
+// structs and enums whose variants come in any of those three shapes, writing
+// them exactly like the standard `#[derive(Debug)]` does, pretty-printing
+// included. The #[debug = "..."] format works on all of their fields.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Mask(&'static str, #[debug = "0b{:04b}"] u8);
+
+#[derive(CustomDebug)]
+pub struct Empty;
+
+#[derive(CustomDebug)]
+pub enum Register {
+    Unused,
+    Flags(#[debug = "0x{:02x}"] u8),
+    Field {
+        name: &'static str,
+        #[debug = "0b{:08b}"]
+        bitmask: u8,
+    },
+    r#Raw {
+        r#type: u8,
+    },
+}
+
+#[derive(CustomDebug)]
+pub enum Never {}
+
+fn main() {
+    assert_eq!(format!("{:?}", Mask("M", 5)), r#"Mask("M", 0b0101)"#);
+    assert_eq!(format!("{:?}", Empty), "Empty");
+
+    assert_eq!(format!("{:?}", Register::Unused), "Unused");
+    assert_eq!(format!("{:?}", Register::Flags(255)), "Flags(0xff)");
+    let field = Register::Field {
+        name: "F",
+        bitmask: 0b00011100,
+    };
+    assert_eq!(
+        format!("{:?}", field),
+        r#"Field { name: "F", bitmask: 0b00011100 }"#,
+    );
+    assert_eq!(
+        format!("{:#?}", field),
+        "Field {\n    name: \"F\",\n    bitmask: 0b00011100,\n}",
+    );
+    assert_eq!(
+        format!("{:?}", Register::Raw { r#type: 1 }),
+        "Raw { type: 1 }"
+    );
+
+    let _ = |never: Never| format!("{:?}", never);
+}
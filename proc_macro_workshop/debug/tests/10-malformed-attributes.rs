@@ -0,0 +1,73 @@
+// Misused #[debug] attributes, and derives on unions, are reported as compile
+// errors pointing at the offending tokens instead of being ignored.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct NotAString {
+    #[debug = 8]
+    bitmask: u8,
+}
+
+#[derive(CustomDebug)]
+pub struct Twice {
+    #[debug = "{:x}"]
+    #[debug = "{:b}"]
+    bitmask: u8,
+}
+
+#[derive(CustomDebug)]
+pub union Bits {
+    signed: i8,
+    unsigned: u8,
+}
+
+#[derive(CustomDebug)]
+pub struct BadBound<T> {
+    #[debug(bound = "T Debug")]
+    value: T,
+}
+
+#[derive(CustomDebug)]
+pub struct SkipRedacted {
+    #[debug(redact)]
+    #[debug(skip)]
+    secret: u8,
+}
+
+#[derive(CustomDebug)]
+pub struct RedactFormatted {
+    #[debug = "{:x}"]
+    #[debug(redact)]
+    secret: u8,
+}
+
+#[derive(CustomDebug)]
+pub struct RedactUnknown {
+    #[debug(redact = "hash")]
+    secret: u8,
+}
+
+#[derive(CustomDebug)]
+pub struct RenameTuple(#[debug(rename = "first")] u8);
+
+#[derive(CustomDebug)]
+pub struct WithFormatted {
+    #[debug(with = "std::fmt::Display::fmt")]
+    #[debug = "{:x}"]
+    value: u8,
+}
+
+#[derive(CustomDebug)]
+pub enum RedactVariant {
+    #[debug(redact)]
+    Secret { key: u8 },
+}
+
+#[derive(CustomDebug)]
+pub enum UnknownOnVariant {
+    #[debug(totally_unknown = 3)]
+    Value(u8),
+}
+
+fn main() {}
@@ -0,0 +1,84 @@
+// Bounds are inferred from every field of every variant, so enums get the
+// same treatment as structs. A #[debug(bound = "...")] attribute on a single
+// field replaces only the bounds inferred for that field, leaving the rest of
+// the inference in place.
+//
+// Here `Handle<T>` is Debug whenever `T` is Display, so the bound on
+// `handle` replaces the inferred `T: Debug` with `T: Display`, while `U: Debug`
+// is still inferred from `Leaf` and `Box<Node<U>>`.
+//
+// A field written through #[debug = "..."] needs the traits its format specs
+// name rather than Debug, e.g. Binary for `0b{:08b}`.
+
+use derive_debug::CustomDebug;
+use std::fmt::{self, Debug, Display};
+use std::marker::PhantomData;
+
+pub struct Handle<T>(T);
+
+impl<T: Display> Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Handle({})", self.0)
+    }
+}
+
+#[derive(CustomDebug)]
+pub enum Tree<T, U> {
+    Leaf(U),
+    Branch {
+        #[debug(bound = "T: Display")]
+        handle: Handle<T>,
+        node: Box<Node<U>>,
+    },
+}
+
+#[derive(CustomDebug)]
+pub struct Node<U> {
+    value: U,
+    #[debug = "{:?}"]
+    children: Vec<(U, [U; 2])>,
+}
+
+#[derive(CustomDebug)]
+pub struct Bits<T> {
+    #[debug = "0b{:08b}"]
+    bits: T,
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct NotDebug;
+
+    struct OnlyDisplay;
+    impl Display for OnlyDisplay {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("only")
+        }
+    }
+
+    struct Flags(u8);
+    impl fmt::Binary for Flags {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            fmt::Binary::fmt(&self.0, f)
+        }
+    }
+
+    assert_debug::<Tree<OnlyDisplay, i32>>();
+    assert_debug::<Node<&str>>();
+    assert_debug::<PhantomData<NotDebug>>();
+    assert_debug::<Bits<Flags>>();
+    assert_eq!(format!("{:?}", Bits { bits: Flags(5) }), "Bits { bits: 0b00000101 }");
+
+    let tree = Tree::Branch {
+        handle: Handle(OnlyDisplay),
+        node: Box::new(Node {
+            value: 1,
+            children: vec![(2, [3, 4])],
+        }),
+    };
+    assert_eq!(
+        format!("{:?}", tree),
+        "Branch { handle: Handle(only), node: Node { value: 1, children: [(2, [3, 4])] } }",
+    );
+}
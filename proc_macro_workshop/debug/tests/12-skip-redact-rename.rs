@@ -0,0 +1,63 @@
+// Fields holding secrets or noise can be kept out of the output.
+//
+//     #[debug(skip)]            leaves the field out entirely
+//     #[debug(redact)]          writes `<redacted>` instead of the value
+//     #[debug(redact = "len")]  writes `<redacted len=N>`, using the value's
+//                               `len()` as a hint
+//     #[debug(rename = "...")]  writes the field under a different name
+//
+// The placeholders are written as-is under every formatting flag, so a
+// secret can't resurface through `{:#?}`. Since skipped and redacted values
+// are never written, their types don't need to implement Debug.
+
+use derive_debug::CustomDebug;
+
+pub struct Key([u8; 32]);
+
+#[derive(CustomDebug)]
+pub struct Credentials {
+    #[debug(rename = "user")]
+    username: String,
+    #[debug(redact = "len")]
+    password: String,
+    #[debug(redact)]
+    key: Key,
+    #[debug(skip)]
+    attempts: u32,
+}
+
+#[derive(CustomDebug)]
+pub enum Auth<T> {
+    Token(#[debug(redact)] T, #[debug(skip)] u64),
+    Anonymous,
+}
+
+fn main() {
+    let credentials = Credentials {
+        username: "admin".to_owned(),
+        password: "hunter2".to_owned(),
+        key: Key([0; 32]),
+        attempts: 3,
+    };
+
+    let debug = format!("{:?}", credentials);
+    assert_eq!(
+        debug,
+        r#"Credentials { user: "admin", password: <redacted len=7>, key: <redacted> }"#,
+    );
+
+    let pretty = format!("{:#?}", credentials);
+    assert_eq!(
+        pretty,
+        r#"Credentials {
+    user: "admin",
+    password: <redacted len=7>,
+    key: <redacted>,
+}"#,
+    );
+    assert!(!pretty.contains("hunter2"));
+
+    let auth = Auth::Token(Key([1; 32]), 42);
+    assert_eq!(format!("{:?}", auth), "Token(<redacted>)");
+    assert_eq!(format!("{:?}", Auth::<Key>::Anonymous), "Anonymous");
+}
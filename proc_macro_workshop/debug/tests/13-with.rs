@@ -0,0 +1,57 @@
+// For formatting beyond what a format string can express, a field can name a
+// function that writes it instead:
+//
+//     #[debug(with = "path::to::function")]
+//
+// The function is called as `fn(&FieldTy, &mut fmt::Formatter) -> fmt::Result`
+// and may be generic. Like skipped and redacted fields, fields written this
+// way don't require their type to implement Debug.
+
+use derive_debug::CustomDebug;
+use std::fmt;
+
+mod fmt_helpers {
+    use std::fmt;
+
+    pub fn hex<T: AsRef<[u8]>>(bytes: &T, f: &mut fmt::Formatter) -> fmt::Result {
+        for byte in bytes.as_ref() {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct Timestamp(u64);
+
+fn clock(time: &Timestamp, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{:02}:{:02}", time.0 / 60, time.0 % 60)
+}
+
+#[derive(CustomDebug)]
+pub struct Packet {
+    #[debug(with = "fmt_helpers::hex")]
+    payload: Vec<u8>,
+    #[debug(with = "clock")]
+    sent: Timestamp,
+}
+
+#[derive(CustomDebug)]
+pub enum Event<'a> {
+    Received(#[debug(with = "fmt_helpers::hex")] &'a [u8], #[debug(with = "clock")] Timestamp),
+    Closed,
+}
+
+fn main() {
+    let packet = Packet {
+        payload: vec![0xde, 0xad, 0xbe, 0xef],
+        sent: Timestamp(754),
+    };
+    assert_eq!(
+        format!("{:?}", packet),
+        "Packet { payload: deadbeef, sent: 12:34 }",
+    );
+
+    let event = Event::Received(&[1, 2], Timestamp(5));
+    assert_eq!(format!("{:?}", event), "Received(0102, 00:05)");
+    assert_eq!(format!("{:?}", Event::Closed), "Closed");
+}
@@ -0,0 +1,115 @@
+// Next to CustomDebug, the crate derives Display from a format string:
+//
+//     #[derive(CustomDisplay)]
+//     #[display("{name} on {port}")]
+//     pub struct Service {
+//         name: String,
+//         port: u16,
+//     }
+//
+// Named fields are interpolated by name and tuple fields by position, with
+// the usual format specs. On enums every variant gets its own format, which
+// a variant with a single field may leave out to forward to that field's
+// Display. Bounds are inferred from the placeholders the fields are used in,
+// so `{value:?}` requires Debug rather than Display. Fields can also give the
+// width or precision of another placeholder, as in `{name:>width$}`.
+
+use derive_debug::CustomDisplay;
+use std::fmt::{self, Debug, Display};
+use std::io;
+
+#[derive(CustomDisplay)]
+#[display("{name} on {port}")]
+pub struct Service {
+    name: String,
+    port: u16,
+}
+
+#[derive(CustomDisplay)]
+#[display("({:.1}, {1:.1})")]
+pub struct Point(f64, f64);
+
+#[derive(CustomDisplay)]
+pub struct Name(String);
+
+#[derive(CustomDisplay)]
+pub struct Kind {
+    r#type: &'static str,
+}
+
+#[derive(CustomDisplay)]
+#[display("{name:>width$}|{ratio:.digits$}")]
+pub struct Column {
+    name: &'static str,
+    width: usize,
+    ratio: f64,
+    digits: usize,
+}
+
+#[derive(CustomDisplay)]
+#[display("{:.*} {2:>0$}")]
+pub struct Reading(usize, f64, char);
+
+// The error enum from sorted's 05-match-expr test, without the hand-written
+// impl.
+#[derive(CustomDisplay)]
+pub enum Error {
+    Fmt(fmt::Error),
+    #[display("io error: {0}")]
+    Io(io::Error),
+    #[display("unexpected {found:?} at {line}")]
+    Unexpected { found: char, line: usize },
+    #[display("end of input")]
+    Eof,
+}
+
+#[derive(CustomDisplay)]
+pub enum Value<T, U> {
+    #[display("{value:?}")]
+    Raw { value: T, marker: std::marker::PhantomData<U> },
+    Shown(U),
+}
+
+fn assert_display<T: Display>() {}
+
+fn main() {
+    let service = Service {
+        name: "db".to_owned(),
+        port: 5432,
+    };
+    assert_eq!(service.to_string(), "db on 5432");
+    assert_eq!(Point(1.0, 2.25).to_string(), "(1.0, 2.2)");
+    assert_eq!(format!("{:>5}", Name("ab".to_owned())), "   ab");
+    assert_eq!(Kind { r#type: "tcp" }.to_string(), "tcp");
+    let column = Column {
+        name: "id",
+        width: 4,
+        ratio: 0.5,
+        digits: 2,
+    };
+    assert_eq!(column.to_string(), "  id|0.50");
+    assert_eq!(Reading(3, 2.25, 'x').to_string(), "2.250   x");
+
+    assert_eq!(Error::Fmt(fmt::Error).to_string(), fmt::Error.to_string());
+    let io = io::Error::new(io::ErrorKind::Other, "disk full");
+    assert_eq!(Error::Io(io).to_string(), "io error: disk full");
+    let unexpected = Error::Unexpected {
+        found: '}',
+        line: 3,
+    };
+    assert_eq!(unexpected.to_string(), "unexpected '}' at 3");
+    assert_eq!(Error::Eof.to_string(), "end of input");
+
+    struct OnlyDebug;
+    impl Debug for OnlyDebug {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("OnlyDebug")
+        }
+    }
+    assert_display::<Value<OnlyDebug, u8>>();
+    let raw = Value::<_, u8>::Raw {
+        value: OnlyDebug,
+        marker: std::marker::PhantomData,
+    };
+    assert_eq!(raw.to_string(), "OnlyDebug");
+}
@@ -0,0 +1,38 @@
+// CustomLowerHex and CustomBinary work just like CustomDisplay, taking their
+// formats from #[lower_hex("...")] and #[binary("...")]. Newtypes leave the
+// format out to forward to their field, flags like `{:#010b}` included.
+
+use derive_debug::{CustomBinary, CustomDisplay, CustomLowerHex};
+
+#[derive(CustomLowerHex, CustomBinary)]
+pub struct Mask(u8);
+
+#[derive(CustomDisplay, CustomLowerHex, CustomBinary)]
+#[display("{r}, {g}, {b}")]
+#[lower_hex("#{r:02x}{g:02x}{b:02x}")]
+#[binary("{r:08b}_{g:08b}_{b:08b}")]
+pub struct Rgb {
+    r: u8,
+    g: u8,
+    b: u8,
+}
+
+#[derive(CustomLowerHex)]
+pub enum Word<T> {
+    #[lower_hex("{0:x}{1:02x}")]
+    Split(T, u8),
+    Whole(T),
+}
+
+fn main() {
+    assert_eq!(format!("{:x}", Mask(0xa5)), "a5");
+    assert_eq!(format!("{:#010b}", Mask(5)), "0b00000101");
+
+    let rgb = Rgb { r: 255, g: 128, b: 0 };
+    assert_eq!(rgb.to_string(), "255, 128, 0");
+    assert_eq!(format!("{:x}", rgb), "#ff8000");
+    assert_eq!(format!("{:b}", rgb), "11111111_10000000_00000000");
+
+    assert_eq!(format!("{:x}", Word::Split(0xab_u8, 0x0c)), "ab0c");
+    assert_eq!(format!("{:x}", Word::<u16>::Whole(0xabc)), "abc");
+}
@@ -0,0 +1,72 @@
+// Missing and misplaced formats of the template-based derives are reported as
+// compile errors.
+
+use derive_debug::{CustomDisplay, CustomLowerHex};
+
+#[derive(CustomDisplay)]
+pub struct Missing {
+    a: u8,
+    b: u8,
+}
+
+#[derive(CustomDisplay)]
+pub enum MissingOnVariant {
+    #[display("one")]
+    One,
+    Two,
+}
+
+#[derive(CustomDisplay)]
+#[display("enum")]
+pub enum OnEnum {
+    A(u8),
+}
+
+#[derive(CustomDisplay)]
+pub struct OnField {
+    #[display("{}")]
+    a: u8,
+}
+
+#[derive(CustomLowerHex)]
+#[lower_hex("{a:x}")]
+#[lower_hex("{a:02x}")]
+pub struct Twice {
+    a: u8,
+}
+
+#[derive(CustomDisplay)]
+pub enum BoundOnVariant<T> {
+    #[display("{0}", bound = "T: Clone")]
+    A(T),
+}
+
+#[derive(CustomDisplay)]
+#[display("{type}")]
+pub struct RawField {
+    r#type: u8,
+}
+
+#[derive(CustomDisplay)]
+#[display("{r#type}")]
+pub struct RawPlaceholder {
+    r#type: u8,
+}
+
+#[derive(CustomDisplay)]
+#[display("{1}")]
+pub struct SkippedPosition(u8, u8);
+
+#[derive(CustomDisplay)]
+pub enum SkippedVariantPosition {
+    #[display("{2} {0}")]
+    A(u8, u8, u8),
+}
+
+#[derive(CustomDisplay)]
+pub union Bits {
+    signed: i8,
+    unsigned: u8,
+}
+
+fn main() {}
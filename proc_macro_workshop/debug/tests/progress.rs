@@ -1,12 +1,20 @@
 #[test]
 fn tests() {
     let t = trybuild::TestCases::new();
-    //t.pass("tests/01-parse.rs");
-    //t.pass("tests/02-impl-debug.rs");
-    //t.pass("tests/03-custom-format.rs");
-    //t.pass("tests/04-type-parameter.rs");
-    //t.pass("tests/05-phantom-data.rs");
-    //t.pass("tests/06-bound-trouble.rs");
-    //t.pass("tests/07-associated-type.rs");
-    //t.pass("tests/08-escape-hatch.rs");
+    t.pass("tests/01-parse.rs");
+    t.pass("tests/02-impl-debug.rs");
+    t.pass("tests/03-custom-format.rs");
+    t.pass("tests/04-type-parameter.rs");
+    t.pass("tests/05-phantom-data.rs");
+    t.pass("tests/06-bound-trouble.rs");
+    t.pass("tests/07-associated-type.rs");
+    t.pass("tests/08-escape-hatch.rs");
+    t.pass("tests/09-tuple-unit-enum.rs");
+    t.compile_fail("tests/10-malformed-attributes.rs");
+    t.pass("tests/11-field-bounds.rs");
+    t.pass("tests/12-skip-redact-rename.rs");
+    t.pass("tests/13-with.rs");
+    t.pass("tests/14-display.rs");
+    t.pass("tests/15-hex-binary.rs");
+    t.compile_fail("tests/16-malformed-templates.rs");
 }